@@ -3,8 +3,12 @@
 //! This module defines the structure and implementation of the Abstract Syntax Tree
 //! used in doc-buildr to represent parsed code elements.
 
+use std::collections::HashMap;
+
+use crate::diagnostics::Diagnostic;
 use crate::entity;
 use crate::parser::*;
+use crate::span::Span;
 
 /// Represents the different types of nodes in the AST.
 #[derive(Debug)]
@@ -12,19 +16,117 @@ pub enum NodeTypes<'a> {
     Enum(&'a entity::Enum),
     Function(&'a entity::Function),
     Struct(&'a entity::Struct),
+    Union(&'a entity::Union),
+    Typedef(&'a entity::Typedef),
+    Macro(&'a entity::Macro),
+    Variable(&'a entity::Variable),
+}
+
+impl<'a> NodeTypes<'a> {
+    /// Returns the kind of the node as a capitalized word, e.g. `Function`.
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            NodeTypes::Enum(_) => "Enum",
+            NodeTypes::Function(_) => "Function",
+            NodeTypes::Struct(_) => "Struct",
+            NodeTypes::Union(_) => "Union",
+            NodeTypes::Typedef(_) => "Typedef",
+            NodeTypes::Macro(_) => "Macro",
+            NodeTypes::Variable(_) => "Variable",
+        }
+    }
+
+    /// Returns the name of the node. For a struct, union or enum this is its
+    /// tag name, which is empty if it only has a typedef alias.
+    pub fn name(&self) -> &'a str {
+        match self {
+            NodeTypes::Enum(x) => &x.name,
+            NodeTypes::Function(x) => &x.name,
+            NodeTypes::Struct(x) => &x.name,
+            NodeTypes::Union(x) => &x.name,
+            NodeTypes::Typedef(x) => &x.name,
+            NodeTypes::Macro(x) => &x.name,
+            NodeTypes::Variable(x) => &x.name,
+        }
+    }
+
+    /// Returns the typedef alias of a struct, union or enum, if it has one.
+    pub fn alias(&self) -> Option<&'a str> {
+        match self {
+            NodeTypes::Enum(x) => x.alias.as_deref(),
+            NodeTypes::Struct(x) => x.alias.as_deref(),
+            NodeTypes::Union(x) => x.alias.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Returns the title of the node, preferring its typedef alias and
+    /// mentioning its tag name alongside the alias when it has both, e.g.
+    /// `point_t (struct point)`. A struct, union or enum with neither is
+    /// titled `(anonymous)`.
+    pub fn title(&self) -> String {
+        let keyword = self.kind().to_lowercase();
+
+        match self.alias() {
+            Some(alias) if self.name().is_empty() => String::from(alias),
+            Some(alias) => format!("{} ({} {})", alias, keyword, self.name()),
+            None if self.name().is_empty() => String::from("(anonymous)"),
+            None => String::from(self.name()),
+        }
+    }
+
+    /// Returns the spellings of the C types the node defines, such as
+    /// `struct node` for a struct and `list_t` for a typedef or an alias.
+    pub fn type_names(&self) -> Vec<String> {
+        let mut names = vec![];
+
+        match self {
+            NodeTypes::Enum(_) | NodeTypes::Struct(_) | NodeTypes::Union(_) => {
+                if !self.name().is_empty() {
+                    names.push(format!("{} {}", self.kind().to_lowercase(), self.name()));
+                }
+                names.extend(self.alias().map(String::from));
+            }
+            NodeTypes::Typedef(x) => names.push(x.name.clone()),
+            _ => {}
+        }
+
+        names
+    }
+
+    /// Returns the anchor of the node's section in generated documentation,
+    /// e.g. `function-list_push`.
+    ///
+    /// The anchor is the slug GitHub generates for a heading made of the
+    /// node's kind and title, so it also matches headings that wrap the title
+    /// in backticks.
+    pub fn anchor(&self) -> String {
+        format!("{} {}", self.kind(), self.title())
+            .to_lowercase()
+            .chars()
+            .filter_map(|c| match c {
+                c if c.is_alphanumeric() || c == '_' || c == '-' => Some(c),
+                c if c.is_whitespace() => Some('-'),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
-/// Represents a node in the AST, containing a comment and a value.
+/// Represents a node in the AST, containing a comment, a value and the
+/// location of the value in the source.
 #[derive(Debug)]
 pub struct Node<'a> {
     comment: Option<&'a entity::DocComment>,
     value: Option<NodeTypes<'a>>,
+    span: Span,
 }
 
 /// Represents the entire Abstract Syntax Tree.
 #[derive(Debug)]
 pub struct AST<'a> {
     ast: Vec<Node<'a>>,
+    file: Option<String>,
 }
 
 impl<'a> Node<'a> {
@@ -33,11 +135,16 @@ impl<'a> Node<'a> {
         Self {
             comment,
             value: match token {
-                ParsedToken::DocComment(_) => None,
+                ParsedToken::DocComment(_) | ParsedToken::Unknown(_) => None,
                 ParsedToken::Struct(x) => Some(NodeTypes::Struct(x)),
+                ParsedToken::Union(x) => Some(NodeTypes::Union(x)),
                 ParsedToken::Function(x) => Some(NodeTypes::Function(x)),
                 ParsedToken::Enum(x) => Some(NodeTypes::Enum(x)),
+                ParsedToken::Typedef(x) => Some(NodeTypes::Typedef(x)),
+                ParsedToken::Macro(x) => Some(NodeTypes::Macro(x)),
+                ParsedToken::Variable(x) => Some(NodeTypes::Variable(x)),
             },
+            span: token.span(),
         }
     }
 
@@ -47,9 +154,14 @@ impl<'a> Node<'a> {
     }
 
     /// Returns the value of this node, if any.
-    pub fn get_value(&self) -> &Option<NodeTypes> {
+    pub fn get_value(&self) -> &Option<NodeTypes<'_>> {
         &self.value
     }
+
+    /// Returns the location of this node's value in the source.
+    pub fn get_span(&self) -> Span {
+        self.span
+    }
 }
 
 impl<'a> AST<'a> {
@@ -63,6 +175,11 @@ impl<'a> AST<'a> {
                 ParsedToken::DocComment(comment) => {
                     current_doc = Some(comment);
                 }
+                // A doc comment in front of an unrecognized declaration
+                // documents that declaration, not the next one.
+                ParsedToken::Unknown(_) => {
+                    current_doc = None;
+                }
                 _ => {
                     ast.push(Node::from(token, current_doc));
                     current_doc = None;
@@ -70,11 +187,120 @@ impl<'a> AST<'a> {
             };
         }
 
-        Self { ast }
+        Self { ast, file: None }
+    }
+
+    /// Sets the path of the source file this AST was built from.
+    pub fn set_file(&mut self, file: &str) {
+        self.file = Some(String::from(file));
+    }
+
+    /// Returns the path of the source file this AST was built from, if set.
+    pub fn get_file(&self) -> Option<&str> {
+        self.file.as_deref()
     }
 
     /// Returns an iterator over the nodes in the AST.
-    pub fn get_iter(&self) -> std::slice::Iter<'_, Node> {
+    pub fn get_iter(&self) -> std::slice::Iter<'_, Node<'_>> {
         self.ast.iter()
     }
+
+    /// Returns the names of all documented symbols in the AST, along with
+    /// the nodes that define them. Structs, unions and enums are listed under
+    /// both their tag name and their typedef alias.
+    pub fn symbols(&self) -> impl Iterator<Item = (&str, &NodeTypes<'_>)> {
+        self.ast
+            .iter()
+            .filter_map(|node| node.value.as_ref())
+            .flat_map(|value| {
+                [Some(value.name()), value.alias()]
+                    .into_iter()
+                    .flatten()
+                    .filter(|name| !name.is_empty())
+                    .map(move |name| (name, value))
+            })
+    }
+
+    /// Checks the inline links in the doc comments of this AST against an
+    /// index of documented symbols.
+    ///
+    /// # Returns
+    ///
+    /// A warning for every link whose target is not in the index, pointing
+    /// at the comment containing the link.
+    pub fn resolve_links(&self, index: &SymbolIndex) -> Vec<Diagnostic> {
+        self.ast
+            .iter()
+            .filter_map(|node| node.comment)
+            .flat_map(|comment| comment.links.iter().map(move |link| (link, comment.span)))
+            .filter(|(link, _)| index.anchor(&link.target).is_none())
+            .map(|(link, span)| {
+                Diagnostic::warning(&format!("unresolved link to `{}`", link.target), span)
+                    .with_help(&format!(
+                        "no documented function, type, macro or variable is named `{}`",
+                        link.target
+                    ))
+            })
+            .collect()
+    }
+}
+
+/// Maps the names of documented symbols to the anchors of their sections in
+/// generated documentation.
+///
+/// An index may be built from several ASTs, so links can refer to symbols
+/// documented in other files. If a name is documented more than once, the
+/// first definition added wins.
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    /// The anchors of symbols by name, including typedef aliases.
+    anchors: HashMap<String, String>,
+
+    /// The anchors of types by their spelling in C, e.g. `struct node`.
+    types: HashMap<String, String>,
+}
+
+impl SymbolIndex {
+    /// Creates a new, empty index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an index of the symbols documented in a single AST.
+    pub fn from_ast(ast: &AST) -> Self {
+        let mut index = Self::new();
+        index.add(ast);
+        index
+    }
+
+    /// Adds the symbols documented in an AST to the index.
+    pub fn add(&mut self, ast: &AST) {
+        for (name, value) in ast.symbols() {
+            self.anchors
+                .entry(String::from(name))
+                .or_insert_with(|| value.anchor());
+        }
+
+        for value in ast.get_iter().filter_map(|node| node.get_value().as_ref()) {
+            for name in value.type_names() {
+                self.types.entry(name).or_insert_with(|| value.anchor());
+            }
+        }
+    }
+
+    /// Returns the anchor of the section documenting the given symbol, if it
+    /// is in the index.
+    pub fn anchor(&self, name: &str) -> Option<&str> {
+        self.anchors.get(name).map(String::as_str)
+    }
+
+    /// Returns the anchor of the section documenting the type with the given
+    /// spelling, such as `struct node` or `list_t`, if it is in the index.
+    ///
+    /// Unlike [`SymbolIndex::anchor`], a struct, union or enum tag is only
+    /// found when spelled with its keyword, as C requires.
+    pub fn type_anchor(&self, spelling: &str) -> Option<&str> {
+        let spelling = spelling.split_whitespace().collect::<Vec<_>>().join(" ");
+        self.types.get(&spelling).map(String::as_str)
+    }
 }
@@ -0,0 +1,271 @@
+//! # Diagnostics Module
+//!
+//! This module renders the problems found while building documentation.
+//! Diagnostics are rendered in the style of `rustc`, with the location, the
+//! offending source line, a caret underline and a help note, or as one JSON
+//! object per line for editors and other tools to consume.
+
+use std::fmt;
+
+use crate::error::{ParseError, ParseErrorKind};
+use crate::span::Span;
+
+/// The ANSI escape sequence that resets all styles.
+const RESET: &str = "\x1b[0m";
+
+/// The ANSI escape sequence for bold text.
+const BOLD: &str = "\x1b[1m";
+
+/// The ANSI escape sequence for the line number gutter.
+const GUTTER: &str = "\x1b[1;34m";
+
+/// Represents how severe a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Something that was skipped, so the documentation is incomplete.
+    Error,
+
+    /// Something that was documented, but probably not as intended.
+    Warning,
+}
+
+impl Severity {
+    /// Returns the name of the severity as a string.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+
+    /// Returns the ANSI escape sequence used to highlight the severity.
+    const fn color(&self) -> &'static str {
+        match self {
+            Severity::Error => "\x1b[1;31m",
+            Severity::Warning => "\x1b[1;33m",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Represents the formats diagnostics can be rendered in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MessageFormat {
+    /// Human-readable reports with source snippets.
+    #[default]
+    Human,
+
+    /// One JSON object per line.
+    Json,
+}
+
+/// Represents a problem found in a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// How severe the problem is.
+    pub severity: Severity,
+
+    /// A description of the problem.
+    pub message: String,
+
+    /// The location of the problem in the source.
+    pub span: Span,
+
+    /// A note on how to fix the problem, if there is one.
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    /// Creates a new error at the given location.
+    pub fn error(message: &str, span: Span) -> Self {
+        Self {
+            severity: Severity::Error,
+            message: String::from(message),
+            span,
+            help: None,
+        }
+    }
+
+    /// Creates a new warning at the given location.
+    pub fn warning(message: &str, span: Span) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message: String::from(message),
+            span,
+            help: None,
+        }
+    }
+
+    /// Returns this diagnostic with a help note attached.
+    pub fn with_help(self, help: &str) -> Self {
+        Self {
+            help: Some(String::from(help)),
+            ..self
+        }
+    }
+
+    /// Renders this diagnostic in the given format.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The path of the file the diagnostic refers to.
+    /// * `src` - The contents of that file, used for source snippets.
+    /// * `format` - The format to render in.
+    /// * `color` - Whether to highlight human-readable output with ANSI colors.
+    pub fn render(&self, file: &str, src: &str, format: MessageFormat, color: bool) -> String {
+        match format {
+            MessageFormat::Human => self.render_human(file, src, color),
+            MessageFormat::Json => self.render_json(file),
+        }
+    }
+
+    /// Renders this diagnostic as a human-readable report.
+    fn render_human(&self, file: &str, src: &str, color: bool) -> String {
+        let style = |code: &'static str| if color { code } else { "" };
+        let (severity, bold, gutter, reset) = (
+            style(self.severity.color()),
+            style(BOLD),
+            style(GUTTER),
+            style(RESET),
+        );
+
+        let mut out = format!(
+            "{}{}{}: {}{}{}\n",
+            severity, self.severity, reset, bold, self.message, reset
+        );
+
+        let Some(line) = src.lines().nth(self.span.line.wrapping_sub(1)) else {
+            out.push_str(format!("{}-->{} {}\n", gutter, reset, file).as_str());
+            return out;
+        };
+
+        let number = self.span.line.to_string();
+        let pad = " ".repeat(number.len());
+
+        out.push_str(
+            format!(
+                "{}{}-->{} {}:{}:{}\n",
+                pad, gutter, reset, file, self.span.line, self.span.column
+            )
+            .as_str(),
+        );
+        out.push_str(format!("{} {}|{}\n", pad, gutter, reset).as_str());
+        out.push_str(format!("{}{} |{} {}\n", gutter, number, reset, line).as_str());
+        out.push_str(
+            format!(
+                "{} {}|{} {}{}{}\n",
+                pad,
+                gutter,
+                reset,
+                severity,
+                underline(line, self.span),
+                reset
+            )
+            .as_str(),
+        );
+
+        if let Some(help) = &self.help {
+            out.push_str(format!("{} {}|{}\n", pad, gutter, reset).as_str());
+            out.push_str(
+                format!(
+                    "{} {}={} {}help{}: {}\n",
+                    pad, gutter, reset, bold, reset, help
+                )
+                .as_str(),
+            );
+        }
+
+        out
+    }
+
+    /// Renders this diagnostic as a single-line JSON object.
+    fn render_json(&self, file: &str) -> String {
+        let help = match &self.help {
+            Some(help) => format!("\"{}\"", json_escape(help)),
+            None => String::from("null"),
+        };
+
+        format!(
+            "{{\"severity\":\"{}\",\"message\":\"{}\",\"file\":\"{}\",\"line\":{},\"column\":{},\"start\":{},\"end\":{},\"help\":{}}}",
+            self.severity,
+            json_escape(&self.message),
+            json_escape(file),
+            self.span.line,
+            self.span.column,
+            self.span.start,
+            self.span.end,
+            help
+        )
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        let help = match error.kind {
+            ParseErrorKind::UnknownToken => {
+                "doc-buildr does not support this kind of declaration; it was skipped"
+            }
+            ParseErrorKind::MalformedComment => {
+                "doc comments must start with `/**` and end with `*/`"
+            }
+            ParseErrorKind::MalformedDeclaration => {
+                "check for unbalanced braces or parentheses; this declaration was skipped"
+            }
+            ParseErrorKind::MissingName => "give the declaration a name to document it",
+        };
+
+        // Skipping a declaration nobody documented loses nothing, so it is
+        // only worth a warning.
+        let diagnostic = if error.documented {
+            Diagnostic::error(&error.message, error.span)
+        } else {
+            Diagnostic::warning(&error.message, error.span)
+        };
+
+        diagnostic.with_help(help)
+    }
+}
+
+/// Returns the caret underline for the part of a line covered by a span.
+///
+/// Tabs before the span are kept, so the carets line up with the source
+/// however wide the terminal renders a tab.
+fn underline(line: &str, span: Span) -> String {
+    let skip = span.column.saturating_sub(1);
+    let mut out = line
+        .chars()
+        .take(skip)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect::<String>();
+
+    let start = line.char_indices().nth(skip).map_or(line.len(), |(i, _)| i);
+    let end = (start + span.end.saturating_sub(span.start)).min(line.len());
+    let len = line.get(start..end).map_or(0, |x| x.chars().count());
+    out.push_str(&"^".repeat(len.max(1)));
+
+    out
+}
+
+/// Escapes a string for use inside a JSON string literal.
+pub(crate) fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(format!("\\u{:04x}", c as u32).as_str()),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
@@ -3,25 +3,77 @@
 //! This module defines the core entities used in doc-buildr to represent
 //! various elements of code structure and documentation.
 
+use crate::span::Span;
+use std::fmt;
+
+/// Represents the direction data flows through a documented parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The parameter is read by the function.
+    In,
+
+    /// The parameter is written by the function.
+    Out,
+
+    /// The parameter is both read and written by the function.
+    InOut,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Direction::In => write!(f, "in"),
+            Direction::Out => write!(f, "out"),
+            Direction::InOut => write!(f, "in, out"),
+        }
+    }
+}
+
 /// Represents a function or method parameter.
-#[derive(Debug)]
+#[derive(Debug, Default, Clone)]
 pub struct Param {
     /// The name of the parameter.
     pub name: String,
 
-    /// The name of the parameter.
+    /// The description of the parameter.
     pub description: String,
+
+    /// The direction of the parameter, if it is annotated as in
+    /// `@param[out]`.
+    pub direction: Option<Direction>,
 }
 
 /// Represents the return value of a function or method.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Return {
     /// The description of the return value.
     pub description: String,
 }
 
+/// Represents one of the values a function or method may return, as
+/// documented by `@retval`.
+#[derive(Debug, Default, Clone)]
+pub struct ReturnValue {
+    /// The returned value, such as `0` or `-EINVAL`.
+    pub value: String,
+
+    /// What returning the value means.
+    pub description: String,
+}
+
+/// Represents an inline link to another documented symbol, written in a doc
+/// comment as `{@link target label}`, `@ref target` or `\ref target`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Link {
+    /// The name of the linked symbol.
+    pub target: String,
+
+    /// The text to show in place of the target's name, if any.
+    pub label: Option<String>,
+}
+
 /// Represents a documentation comment.
-#[derive(Debug)]
+#[derive(Debug, Default, Clone)]
 pub struct DocComment {
     /// The main body of the comment.
     pub comment: String,
@@ -31,35 +83,568 @@ pub struct DocComment {
 
     /// An optional description of the return value.
     pub retval: Option<Return>,
+
+    /// The individually documented return values, in the order given.
+    pub retvals: Vec<ReturnValue>,
+
+    /// An optional one-line summary, given by `@brief`.
+    pub brief: Option<String>,
+
+    /// Notes, given by `@note`.
+    pub notes: Vec<String>,
+
+    /// Warnings, given by `@warning`.
+    pub warnings: Vec<String>,
+
+    /// References to related code or documents, given by `@see`.
+    pub see: Vec<String>,
+
+    /// The version the documented code first appeared in, given by `@since`.
+    pub since: Option<String>,
+
+    /// Why the documented code is deprecated, given by `@deprecated`.
+    ///
+    /// The text is empty if the code is deprecated without explanation.
+    pub deprecated: Option<String>,
+
+    /// The errors or exceptions that may be raised, given by `@throws`.
+    pub throws: Vec<String>,
+
+    /// The authors of the documented code, given by `@author`.
+    pub authors: Vec<String>,
+
+    /// Outstanding work, given by `@todo`.
+    pub todos: Vec<String>,
+
+    /// Preconditions, given by `@pre`.
+    pub pre: Vec<String>,
+
+    /// Postconditions, given by `@post`.
+    pub post: Vec<String>,
+
+    /// The inline links in the description, then in each tag in the order
+    /// the fields are declared.
+    pub links: Vec<Link>,
+
+    /// The location of the comment in the source.
+    pub span: Span,
+}
+
+impl DocComment {
+    /// Returns the text of the description and of every tag, in the order
+    /// the fields are declared, as visited by [`DocComment::map_text`].
+    pub fn texts(&self) -> Vec<&str> {
+        let mut texts = vec![self.comment.as_str()];
+        texts.extend(self.params.iter().map(|param| param.description.as_str()));
+        texts.extend(self.retval.iter().map(|retval| retval.description.as_str()));
+        texts.extend(
+            self.retvals
+                .iter()
+                .map(|retval| retval.description.as_str()),
+        );
+        texts.extend(self.brief.as_deref());
+
+        for list in [&self.notes, &self.warnings, &self.see] {
+            texts.extend(list.iter().map(String::as_str));
+        }
+
+        texts.extend(self.since.as_deref());
+        texts.extend(self.deprecated.as_deref());
+
+        for list in [
+            &self.throws,
+            &self.authors,
+            &self.todos,
+            &self.pre,
+            &self.post,
+        ] {
+            texts.extend(list.iter().map(String::as_str));
+        }
+
+        texts
+    }
+
+    /// Returns a copy of this comment with `f` applied to the text of the
+    /// description and of every tag, in the order the fields are declared.
+    pub fn map_text(&self, mut f: impl FnMut(&str) -> String) -> Self {
+        Self {
+            comment: f(&self.comment),
+            params: self
+                .params
+                .iter()
+                .map(|param| Param {
+                    description: f(&param.description),
+                    ..param.clone()
+                })
+                .collect(),
+            retval: self.retval.as_ref().map(|retval| Return {
+                description: f(&retval.description),
+            }),
+            retvals: self
+                .retvals
+                .iter()
+                .map(|retval| ReturnValue {
+                    description: f(&retval.description),
+                    ..retval.clone()
+                })
+                .collect(),
+            brief: self.brief.as_deref().map(&mut f),
+            notes: self.notes.iter().map(|x| f(x)).collect(),
+            warnings: self.warnings.iter().map(|x| f(x)).collect(),
+            see: self.see.iter().map(|x| f(x)).collect(),
+            since: self.since.as_deref().map(&mut f),
+            deprecated: self.deprecated.as_deref().map(&mut f),
+            throws: self.throws.iter().map(|x| f(x)).collect(),
+            authors: self.authors.iter().map(|x| f(x)).collect(),
+            todos: self.todos.iter().map(|x| f(x)).collect(),
+            pre: self.pre.iter().map(|x| f(x)).collect(),
+            post: self.post.iter().map(|x| f(x)).collect(),
+            links: self.links.clone(),
+            span: self.span,
+        }
+    }
+}
+
+/// Represents a member of a struct.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Member {
+    /// The type of the member, including its qualifiers and pointer depth.
+    pub ty: Type,
+
+    /// The name of the member, if it is named.
+    pub name: Option<String>,
+
+    /// The array extents of the member, e.g. `["32"]` for `char name[32]`.
+    pub arrays: Vec<String>,
+
+    /// The array extents of the pointed-to array, if this is a pointer to an
+    /// array, e.g. `["4"]` for `int (*rows)[4]`.
+    pub pointee_arrays: Vec<String>,
+
+    /// The parameters of the pointed-to function, if this is a function pointer.
+    pub function: Option<Vec<Parameter>>,
+
+    /// The width of the member, if it is a bitfield.
+    pub bitfield: Option<String>,
+
+    /// The definition of the member's type, if it is declared inline.
+    pub aggregate: Option<Box<Aggregate>>,
+
+    /// The description from the member's doc comment, if any.
+    pub description: Option<String>,
+}
+
+impl fmt::Display for Member {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // An inline struct or union without a tag is spelled with an elided
+        // body, so that it does not read as a type named after the member.
+        let anonymous;
+        let ty = match self.aggregate.as_deref() {
+            Some(aggregate) if aggregate.name().is_empty() => {
+                anonymous = Type {
+                    name: format!("{} {{ ... }}", self.ty.name),
+                    ..self.ty.clone()
+                };
+                &anonymous
+            }
+            _ => &self.ty,
+        };
+
+        fmt_declarator(
+            f,
+            ty,
+            self.name.as_deref(),
+            &self.arrays,
+            &self.pointee_arrays,
+            self.function.as_deref(),
+        )?;
+
+        if let Some(width) = &self.bitfield {
+            write!(f, " : {}", width)?;
+        }
+
+        Ok(())
+    }
 }
 
 /// Represents a struct definition.
-#[derive(Debug)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Struct {
-    /// The name of the struct.
+    /// The name of the struct, which is empty for an anonymous struct.
     pub name: String,
 
+    /// The alias given to the struct by an enclosing `typedef`, if any.
+    pub alias: Option<String>,
+
     /// A list of the struct's members.
-    pub members: Vec<String>,
+    pub members: Vec<Member>,
+
+    /// The location of the struct in the source.
+    pub span: Span,
+}
+
+/// Represents a union definition.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Union {
+    /// The name of the union, which is empty for an anonymous union.
+    pub name: String,
+
+    /// The alias given to the union by an enclosing `typedef`, if any.
+    pub alias: Option<String>,
+
+    /// A list of the union's members.
+    pub members: Vec<Member>,
+
+    /// The location of the union in the source.
+    pub span: Span,
+}
+
+/// Represents a struct or union defined inline as the type of a member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Aggregate {
+    Struct(Struct),
+    Union(Union),
+}
+
+impl Aggregate {
+    /// Returns the tag name of the struct or union, which is empty if it is
+    /// anonymous.
+    pub fn name(&self) -> &str {
+        match self {
+            Aggregate::Struct(x) => &x.name,
+            Aggregate::Union(x) => &x.name,
+        }
+    }
+
+    /// Returns the members of the struct or union.
+    pub fn members(&self) -> &[Member] {
+        match self {
+            Aggregate::Struct(x) => &x.members,
+            Aggregate::Union(x) => &x.members,
+        }
+    }
+}
+
+/// Represents a C type as written in a declaration.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Type {
+    /// Qualifiers of the base type, such as `const` and `volatile`.
+    pub qualifiers: Vec<String>,
+
+    /// The base type name, e.g. `unsigned long` or `struct node`.
+    pub name: String,
+
+    /// The qualifiers of each pointer level, outermost last, e.g.
+    /// `[[], ["const"]]` for `char **const`.
+    pub pointers: Vec<Vec<String>>,
+}
+
+impl Type {
+    /// Returns whether this is the `void` type.
+    pub fn is_void(&self) -> bool {
+        self.name == "void" && self.pointers.is_empty()
+    }
+
+    /// Returns a declaration of `name` with this type, e.g. `const char *name`.
+    pub fn declare(&self, name: &str) -> String {
+        let qualified = matches!(self.pointers.last(), Some(level) if !level.is_empty());
+
+        if (self.pointers.is_empty() || qualified) && !name.is_empty() {
+            format!("{} {}", self, name)
+        } else {
+            format!("{}{}", self, name)
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for qualifier in &self.qualifiers {
+            write!(f, "{} ", qualifier)?;
+        }
+
+        write!(f, "{}", self.name)?;
+
+        if !self.pointers.is_empty() {
+            write!(f, " ")?;
+        }
+
+        for (i, level) in self.pointers.iter().enumerate() {
+            // A qualifier is separated from the `*` of the next level.
+            if i > 0 && !self.pointers[i - 1].is_empty() {
+                write!(f, " ")?;
+            }
+
+            write!(f, "*{}", level.join(" "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents a parameter in a function declaration.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Parameter {
+    /// The type of the parameter, including its qualifiers and pointer depth.
+    pub ty: Type,
+
+    /// The name of the parameter, if it is named.
+    pub name: Option<String>,
+
+    /// The array extents of the parameter, e.g. `["10"]` for `int arr[10]`.
+    pub arrays: Vec<String>,
+
+    /// The array extents of the pointed-to array, if this is a pointer to an
+    /// array, e.g. `["4"]` for `int (*mat)[4]`.
+    pub pointee_arrays: Vec<String>,
+
+    /// The parameters of the pointed-to function, if this is a function pointer.
+    pub function: Option<Vec<Parameter>>,
+}
+
+impl Parameter {
+    /// Returns whether this is the `...` of a variadic function.
+    pub fn is_variadic(&self) -> bool {
+        self.ty.name == "..."
+    }
+}
+
+impl fmt::Display for Parameter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt_declarator(
+            f,
+            &self.ty,
+            self.name.as_deref(),
+            &self.arrays,
+            &self.pointee_arrays,
+            self.function.as_deref(),
+        )
+    }
+}
+
+/// Returns the parameters of a function separated by commas, or `void` if
+/// there are none, since `()` leaves the parameters unspecified in C.
+pub fn param_list(params: &[Parameter]) -> String {
+    if params.is_empty() {
+        return String::from("void");
+    }
+
+    params
+        .iter()
+        .map(Parameter::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Formats a declarator such as `char *name[4]`, `void (*cb)(int)` or
+/// `int (*mat)[4]`.
+fn fmt_declarator(
+    f: &mut fmt::Formatter<'_>,
+    ty: &Type,
+    name: Option<&str>,
+    arrays: &[String],
+    pointee_arrays: &[String],
+    function: Option<&[Parameter]>,
+) -> fmt::Result {
+    let name = name.unwrap_or("");
+    let parenthesized = function.is_some() || !pointee_arrays.is_empty();
+
+    if parenthesized {
+        write!(f, "{}", ty.declare(&format!("(*{}", name)))?;
+    } else {
+        write!(f, "{}", ty.declare(name))?;
+    }
+
+    for extent in arrays {
+        write!(f, "[{}]", extent)?;
+    }
+
+    if parenthesized {
+        write!(f, ")")?;
+    }
+
+    for extent in pointee_arrays {
+        write!(f, "[{}]", extent)?;
+    }
+
+    if let Some(params) = function {
+        write!(f, "({})", param_list(params))?;
+    }
+
+    Ok(())
 }
 
 /// Represents a function definition.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Function {
     /// The name of the function.
     pub name: String,
+
+    /// The storage-class specifier of the function, such as `static` or `extern`.
+    pub storage: Option<String>,
+
+    /// Function specifiers such as `inline` and `_Noreturn`.
+    pub specifiers: Vec<String>,
+
+    /// Attributes such as `__attribute__((pure))` or `[[nodiscard]]`.
+    pub attributes: Vec<String>,
+
     /// The return type of the function.
-    pub return_type: String,
+    pub return_type: Type,
+
     /// A list of the function's parameters.
-    pub params: Vec<String>,
+    pub params: Vec<Parameter>,
+
+    /// The location of the function in the source.
+    pub span: Span,
+}
+
+/// Represents a variant of an enum.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Variant {
+    /// The name of the variant.
+    pub name: String,
+
+    /// The explicit value expression of the variant, if any.
+    pub value: Option<String>,
+
+    /// The description from the variant's doc comment, if any.
+    pub description: Option<String>,
 }
 
 /// Represents an enum definition.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Enum {
-    /// The name of the enum.
+    /// The name of the enum, which is empty for an anonymous enum.
     pub name: String,
 
+    /// The alias given to the enum by an enclosing `typedef`, if any.
+    pub alias: Option<String>,
+
     /// A list of the enum's variants.
-    pub variants: Vec<String>,
+    pub variants: Vec<Variant>,
+
+    /// The location of the enum in the source.
+    pub span: Span,
+}
+
+/// Represents a typedef of a type other than an inline struct, union or enum.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Typedef {
+    /// The alias introduced by the typedef.
+    pub name: String,
+
+    /// The target type, including its qualifiers and pointer depth.
+    pub ty: Type,
+
+    /// The array extents of the target type, e.g. `["16"]` for `typedef char id_t[16]`.
+    pub arrays: Vec<String>,
+
+    /// The array extents of the pointed-to array, if this is a pointer to an
+    /// array typedef, e.g. `["4"]` for `typedef int (*row_t)[4]`.
+    pub pointee_arrays: Vec<String>,
+
+    /// The parameters of the pointed-to function, if this is a function pointer typedef.
+    pub function: Option<Vec<Parameter>>,
+
+    /// The location of the typedef in the source.
+    pub span: Span,
+}
+
+impl fmt::Display for Typedef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "typedef ")?;
+        fmt_declarator(
+            f,
+            &self.ty,
+            Some(&self.name),
+            &self.arrays,
+            &self.pointee_arrays,
+            self.function.as_deref(),
+        )
+    }
+}
+
+/// Represents a `#define` macro.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Macro {
+    /// The name of the macro.
+    pub name: String,
+
+    /// The parameters of a function-like macro, or `None` for an object-like macro.
+    pub params: Option<Vec<String>>,
+
+    /// The replacement text of the macro.
+    pub value: String,
+
+    /// The location of the macro in the source.
+    pub span: Span,
+}
+
+impl fmt::Display for Macro {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#define {}", self.name)?;
+
+        if let Some(params) = &self.params {
+            write!(f, "({})", params.join(", "))?;
+        }
+
+        if !self.value.is_empty() {
+            write!(f, " {}", self.value)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Represents a global variable or constant declaration.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Variable {
+    /// The name of the variable.
+    pub name: String,
+
+    /// The storage-class specifier of the variable, such as `static` or `extern`.
+    pub storage: Option<String>,
+
+    /// The type of the variable, including its qualifiers and pointer depth.
+    pub ty: Type,
+
+    /// The array extents of the variable, e.g. `["16"]` for `int table[16]`.
+    pub arrays: Vec<String>,
+
+    /// The array extents of the pointed-to array, if this is a pointer to an
+    /// array, e.g. `["3"]` for `int (*arr)[3]`.
+    pub pointee_arrays: Vec<String>,
+
+    /// The parameters of the pointed-to function, if this is a function pointer.
+    pub function: Option<Vec<Parameter>>,
+
+    /// The initializer of the variable, if any.
+    pub initializer: Option<String>,
+
+    /// The location of the variable in the source.
+    pub span: Span,
+}
+
+impl fmt::Display for Variable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(storage) = &self.storage {
+            write!(f, "{} ", storage)?;
+        }
+
+        fmt_declarator(
+            f,
+            &self.ty,
+            Some(&self.name),
+            &self.arrays,
+            &self.pointee_arrays,
+            self.function.as_deref(),
+        )?;
+
+        if let Some(initializer) = &self.initializer {
+            write!(f, " = {}", initializer)?;
+        }
+
+        Ok(())
+    }
 }
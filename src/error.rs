@@ -0,0 +1,86 @@
+//! # Error Module
+//!
+//! This module defines the errors reported while tokenizing and parsing
+//! source code. Errors are recoverable: a declaration that cannot be parsed
+//! is reported and skipped, and the rest of the source is still documented.
+
+use std::fmt;
+
+use crate::span::Span;
+
+/// Represents the kinds of errors that can occur while parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A declaration did not match any known token, such as the static
+    /// assertion `_Static_assert(sizeof(int) == 4, "int");`.
+    UnknownToken,
+
+    /// A doc comment is missing its `/**` or `*/` delimiter.
+    MalformedComment,
+
+    /// A declaration could not be broken into its parts, for example because
+    /// of an unbalanced brace or parenthesis.
+    MalformedDeclaration,
+
+    /// A declaration does not declare a name, such as the forward declaration
+    /// `struct node;`.
+    MissingName,
+}
+
+/// Represents an error that occurred while parsing a token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The kind of the error.
+    pub kind: ParseErrorKind,
+
+    /// The location of the offending token in the source.
+    pub span: Span,
+
+    /// A human-readable description of the error.
+    pub message: String,
+
+    /// Whether the offending token is a doc comment or a declaration with
+    /// one, so skipping it loses documentation.
+    pub documented: bool,
+}
+
+impl ParseError {
+    /// Creates a new error with an empty span.
+    ///
+    /// Parsers only see the text of a token, so the caller is expected to
+    /// fill the span in using [`ParseError::with_span`].
+    pub fn new(kind: ParseErrorKind, message: &str) -> Self {
+        Self {
+            kind,
+            span: Span::default(),
+            message: String::from(message),
+            documented: false,
+        }
+    }
+
+    /// Returns this error with its span set.
+    pub fn with_span(self, span: Span) -> Self {
+        Self { span, ..self }
+    }
+
+    /// Returns this error marked as losing documentation, or not.
+    pub fn with_documented(self, documented: bool) -> Self {
+        Self { documented, ..self }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.span.line == 0 {
+            write!(f, "{}", self.message)
+        } else {
+            write!(
+                f,
+                "{}:{}: {}",
+                self.span.line, self.span.column, self.message
+            )
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
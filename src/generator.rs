@@ -0,0 +1,326 @@
+//! # Generator Module
+//!
+//! This module defines the interface shared by output backends. A backend is
+//! a [`Generator`] that is walked over the ASTs of every input file, and
+//! backends are looked up by name in a [`Registry`], so library users can add
+//! their own formats alongside the built-in markdown, HTML, JSON and man page
+//! ones.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::ast::{Node, NodeTypes, SymbolIndex, AST};
+use crate::entity;
+use crate::html_gen::HtmlGenerator;
+use crate::json_gen::JsonGenerator;
+use crate::man_gen::ManGenerator;
+use crate::md_gen::MarkdownGenerator;
+use crate::parser::replace_links;
+
+/// Represents a file of a generated site.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    /// The path of the file, relative to the root of the site.
+    pub path: String,
+
+    /// The contents of the file.
+    pub contents: String,
+}
+
+/// Represents the documentation produced by a generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    /// A single document, written to a file or to stdout.
+    Document(String),
+
+    /// A set of files, written to a directory.
+    Site(Vec<Page>),
+}
+
+/// An output backend, walked over the ASTs of every input file by
+/// [`generate`].
+///
+/// Every method but [`Generator::finish`] does nothing by default, so a
+/// backend only implements the hooks for what it documents.
+pub trait Generator {
+    /// Called once with every AST before any module is visited, for backends
+    /// that need to know about all modules up front, such as to link between
+    /// pages.
+    fn begin(&mut self, _asts: &[AST]) {}
+
+    /// Called before the nodes of a module are visited.
+    fn begin_module(&mut self, _ast: &AST) {}
+
+    /// Visits a node, calling the `visit_*` method for the kind of its value.
+    fn visit_node(&mut self, node: &Node) {
+        match node.get_value() {
+            Some(NodeTypes::Function(x)) => self.visit_function(node, x),
+            Some(NodeTypes::Struct(x)) => self.visit_struct(node, x),
+            Some(NodeTypes::Union(x)) => self.visit_union(node, x),
+            Some(NodeTypes::Enum(x)) => self.visit_enum(node, x),
+            Some(NodeTypes::Typedef(x)) => self.visit_typedef(node, x),
+            Some(NodeTypes::Macro(x)) => self.visit_macro(node, x),
+            Some(NodeTypes::Variable(x)) => self.visit_variable(node, x),
+            None => {}
+        }
+    }
+
+    /// Visits a function.
+    fn visit_function(&mut self, _node: &Node, _function: &entity::Function) {}
+
+    /// Visits a struct.
+    fn visit_struct(&mut self, _node: &Node, _struct: &entity::Struct) {}
+
+    /// Visits a union.
+    fn visit_union(&mut self, _node: &Node, _union: &entity::Union) {}
+
+    /// Visits an enum.
+    fn visit_enum(&mut self, _node: &Node, _enum: &entity::Enum) {}
+
+    /// Visits a typedef.
+    fn visit_typedef(&mut self, _node: &Node, _typedef: &entity::Typedef) {}
+
+    /// Visits a macro.
+    fn visit_macro(&mut self, _node: &Node, _macro: &entity::Macro) {}
+
+    /// Visits a global variable.
+    fn visit_variable(&mut self, _node: &Node, _variable: &entity::Variable) {}
+
+    /// Called after the nodes of a module are visited.
+    fn end_module(&mut self, _ast: &AST) {}
+
+    /// Returns the documentation generated from every module visited.
+    fn finish(&mut self) -> Output;
+
+    /// Returns the directory a site is written to if no output is given. The
+    /// current directory by default.
+    fn default_dir(&self) -> &str {
+        "."
+    }
+}
+
+/// Walks a generator over the ASTs of every input file, without finishing
+/// it.
+pub fn walk(generator: &mut dyn Generator, asts: &[AST]) {
+    generator.begin(asts);
+
+    for ast in asts {
+        generator.begin_module(ast);
+
+        for node in ast.get_iter() {
+            generator.visit_node(node);
+        }
+
+        generator.end_module(ast);
+    }
+}
+
+/// Generates documentation for the ASTs of every input file.
+///
+/// # Arguments
+///
+/// * `generator` - The backend to generate documentation with.
+/// * `asts` - The ASTs to document, in the order their files were given.
+///
+/// # Returns
+///
+/// The documentation returned by [`Generator::finish`].
+pub fn generate(generator: &mut dyn Generator, asts: &[AST]) -> Output {
+    walk(generator, asts);
+    generator.finish()
+}
+
+/// Creates a generator that links to the symbols in the given index.
+pub type Factory = Box<dyn Fn(&SymbolIndex) -> Box<dyn Generator + '_>>;
+
+/// Represents the output backends available by name.
+#[derive(Default)]
+pub struct Registry {
+    /// The backends in the order they were registered, by name.
+    generators: Vec<(String, Factory)>,
+}
+
+impl Registry {
+    /// Creates a new, empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry of the built-in `markdown`, `html`, `json` and
+    /// `man` backends.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register("markdown", |index| Box::new(MarkdownGenerator::new(index)));
+        registry.register("html", |index| Box::new(HtmlGenerator::new(index)));
+        registry.register("json", |_| Box::new(JsonGenerator::new()));
+        registry.register("man", |index| Box::new(ManGenerator::new(index)));
+        registry
+    }
+
+    /// Registers a backend under a name, replacing any backend registered
+    /// under the same name before.
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&SymbolIndex) -> Box<dyn Generator + '_> + 'static,
+    ) {
+        let factory: Factory = Box::new(factory);
+
+        match self.generators.iter_mut().find(|(x, _)| x == name) {
+            Some((_, existing)) => *existing = factory,
+            None => self.generators.push((String::from(name), factory)),
+        }
+    }
+
+    /// Returns the names of the registered backends, in the order they were
+    /// registered.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.generators.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// Creates the backend registered under a name, if there is one.
+    pub fn create<'i>(
+        &self,
+        name: &str,
+        index: &'i SymbolIndex,
+    ) -> Option<Box<dyn Generator + 'i>> {
+        self.generators
+            .iter()
+            .find(|(x, _)| x == name)
+            .map(|(_, factory)| factory(index))
+    }
+}
+
+/// The longest macro replacement or initializer text shown in full; longer
+/// text is elided from the generated signature.
+const VALUE_LIMIT: usize = 60;
+
+/// Elides macro replacement or initializer text longer than `VALUE_LIMIT`.
+pub(crate) fn summarize(value: &str) -> String {
+    if value.len() <= VALUE_LIMIT {
+        String::from(value)
+    } else if value.starts_with('{') {
+        String::from("{ ... }")
+    } else {
+        String::from("...")
+    }
+}
+
+/// Formats the signature of a function as it would appear in a prototype.
+pub(crate) fn function_signature(function: &entity::Function) -> String {
+    let mut signature = String::new();
+
+    for specifier in function.storage.iter().chain(&function.specifiers) {
+        signature.push_str(specifier);
+        signature.push(' ');
+    }
+
+    signature.push_str(&function.return_type.declare(&function.name));
+    signature.push_str(format!("({})", entity::param_list(&function.params)).as_str());
+
+    for attribute in &function.attributes {
+        signature.push(' ');
+        signature.push_str(attribute);
+    }
+
+    signature
+}
+
+/// Returns the first line of the brief of a doc comment, or of its
+/// description if it has no brief, with inline links replaced by their text.
+pub(crate) fn summary(comment: &entity::DocComment) -> String {
+    let text = comment.brief.as_deref().unwrap_or(&comment.comment);
+
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(|line| {
+            replace_links(line, |link| {
+                link.label.clone().unwrap_or_else(|| link.target.clone())
+            })
+        })
+        .unwrap_or_default()
+}
+
+/// Splits a C snippet into runs of plain text and the names of documented
+/// types, pairing each type name with the anchor of its section.
+///
+/// The type named `own` is never linked, so a typedef does not link to
+/// itself.
+pub(crate) fn type_runs<'s, 'i>(
+    code: &'s str,
+    index: &'i SymbolIndex,
+    own: &str,
+) -> Vec<(&'s str, Option<&'i str>)> {
+    static RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"\b(?:(?:struct|union|enum)\s+)?[A-Za-z_]\w*\b").unwrap());
+
+    let mut runs = vec![];
+    let mut last = 0;
+
+    for m in RE.find_iter(code) {
+        let Some(anchor) = index.type_anchor(m.as_str()).filter(|_| m.as_str() != own) else {
+            continue;
+        };
+
+        if last < m.start() {
+            runs.push((&code[last..m.start()], None));
+        }
+
+        runs.push((m.as_str(), Some(anchor)));
+        last = m.end();
+    }
+
+    if last < code.len() {
+        runs.push((&code[last..], None));
+    }
+
+    runs
+}
+
+/// Returns the run of backticks or tildes that opens or closes a fenced code
+/// block on a line, if the line is a fence.
+pub(crate) fn fence_marker(line: &str) -> Option<&str> {
+    let text = line.trim_start();
+
+    let marker = match text.chars().next() {
+        Some(c @ ('`' | '~')) => &text[..text.len() - text.trim_start_matches(c).len()],
+        _ => return None,
+    };
+
+    (marker.len() >= 3).then_some(marker)
+}
+
+/// Returns a unique name for a module, named after the stem of its file,
+/// given the names already taken.
+pub(crate) fn module_name(ast: &AST, taken: &mut HashSet<String>) -> String {
+    let stem = ast
+        .get_file()
+        .and_then(|file| Path::new(file).file_stem())
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or("module");
+
+    let mut name = String::from(stem);
+    let mut n = 1;
+
+    while taken.contains(&name) {
+        n += 1;
+        name = format!("{}-{}", stem, n);
+    }
+
+    taken.insert(name.clone());
+
+    name
+}
+
+/// Escapes text for use in HTML, including in a quoted attribute value.
+pub(crate) fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
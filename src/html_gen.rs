@@ -0,0 +1,936 @@
+//! # HTML Generation Module
+//!
+//! This module is responsible for generating a static HTML site from the
+//! Abstract Syntax Trees (ASTs) of one or more source files. The site has one
+//! page per module, a sidebar listing every documented symbol, and a search
+//! box backed by a client-side search index.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{Node, SymbolIndex, AST};
+use crate::diagnostics::json_escape;
+use crate::entity;
+use crate::generator::{
+    fence_marker, function_signature, html_escape, module_name, summarize, summary, type_runs,
+    walk, Generator, Output, Page,
+};
+use crate::parser::replace_links;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// The stylesheet shared by every page of the site.
+const STYLE: &str = r#"* { box-sizing: border-box; }
+body { display: flex; margin: 0; font-family: sans-serif; line-height: 1.5; color: #1f2328; }
+nav.sidebar { position: sticky; top: 0; flex: 0 0 16rem; height: 100vh; overflow-y: auto; padding: 1rem; background: #f6f8fa; border-right: 1px solid #d0d7de; }
+nav.sidebar h2, nav.sidebar h3 { margin: 1rem 0 0.25rem; font-size: 0.9rem; text-transform: uppercase; }
+nav.sidebar ul { margin: 0; padding: 0; list-style: none; }
+nav.sidebar a { color: inherit; text-decoration: none; }
+nav.sidebar a:hover, nav.sidebar .current > a { text-decoration: underline; }
+#search { width: 100%; padding: 0.25rem; }
+#search-results .kind { margin-right: 0.5rem; color: #656d76; font-size: 0.8rem; }
+main { flex: 1; max-width: 60rem; padding: 1rem 2rem; }
+section.item { padding-top: 1rem; border-top: 1px solid #d0d7de; }
+pre { padding: 0.75rem; overflow-x: auto; background: #f6f8fa; border-radius: 6px; }
+code { font-family: monospace; }
+pre .kw { color: #cf222e; }
+pre .lit { color: #0a3069; }
+pre a.type { color: #8250df; }
+table { border-collapse: collapse; }
+th, td { padding: 0.25rem 0.75rem; border: 1px solid #d0d7de; text-align: left; }
+.deprecated { color: #9a6700; }
+.source { color: #656d76; font-size: 0.9rem; }
+"#;
+
+/// The script that filters the search index as the user types.
+const SEARCH: &str = r#"(function () {
+    var input = document.getElementById("search");
+    var results = document.getElementById("search-results");
+
+    input.addEventListener("input", function () {
+        var query = input.value.trim().toLowerCase();
+        results.innerHTML = "";
+
+        if (!query) {
+            return;
+        }
+
+        searchIndex
+            .filter(function (item) {
+                return item.name.toLowerCase().indexOf(query) !== -1;
+            })
+            .sort(function (a, b) {
+                return a.name.toLowerCase().indexOf(query) - b.name.toLowerCase().indexOf(query);
+            })
+            .slice(0, 20)
+            .forEach(function (item) {
+                var li = document.createElement("li");
+                var kind = document.createElement("span");
+                var link = document.createElement("a");
+
+                kind.className = "kind";
+                kind.textContent = item.kind;
+                link.href = item.href;
+                link.textContent = item.name;
+                li.title = item.desc;
+                li.appendChild(kind);
+                li.appendChild(link);
+                results.appendChild(li);
+            });
+    });
+})();
+"#;
+
+/// The C keywords highlighted in signatures.
+const KEYWORDS: &[&str] = &[
+    "_Bool",
+    "_Noreturn",
+    "auto",
+    "bool",
+    "char",
+    "const",
+    "double",
+    "enum",
+    "extern",
+    "float",
+    "inline",
+    "int",
+    "long",
+    "register",
+    "restrict",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "typedef",
+    "union",
+    "unsigned",
+    "void",
+    "volatile",
+];
+
+/// Represents a source file documented on its own page.
+struct Module {
+    /// The name of the module, taken from the stem of its file name.
+    name: String,
+
+    /// The path of the module's page, relative to the root of the site.
+    page: String,
+
+    /// The number of symbols the module documents.
+    count: usize,
+}
+
+/// Represents a documented symbol, as listed in the sidebar and the search
+/// index.
+struct Symbol {
+    /// The kind of the symbol, e.g. `Function`.
+    kind: &'static str,
+
+    /// The title of the symbol's section.
+    title: String,
+
+    /// The name of the module documenting the symbol.
+    module: String,
+
+    /// The link to the symbol's section.
+    href: String,
+
+    /// The first line of the symbol's brief or description.
+    desc: String,
+}
+
+/// Generates a static HTML site: an `index.html` listing the modules, a page
+/// per module, the search index and the shared stylesheet and script.
+pub struct HtmlGenerator<'i> {
+    /// The symbols that inline links and type names may refer to.
+    index: &'i SymbolIndex,
+
+    /// The modules of the site, in the order their files were given.
+    modules: Vec<Module>,
+
+    /// The page each section anchor is on. As in the symbol index, the first
+    /// definition of a symbol wins.
+    pages: HashMap<String, String>,
+
+    /// The symbols documented across all modules.
+    symbols: Vec<Symbol>,
+
+    /// The position of the module being visited in `modules`.
+    current: usize,
+
+    /// The path of the file of the module being visited, if set.
+    file: Option<String>,
+
+    /// The body of the page of the module being visited.
+    body: String,
+
+    /// The pages of the modules visited so far.
+    out: Vec<Page>,
+}
+
+/// Generates a static HTML site from the ASTs of one or more source files.
+///
+/// # Arguments
+///
+/// * `asts` - The ASTs to document, one page per AST.
+/// * `index` - The symbols that inline links and type names may refer to.
+///
+/// # Returns
+///
+/// The files of the site, as described in [`HtmlGenerator`].
+pub fn generate_site(asts: &[AST], index: &SymbolIndex) -> Vec<Page> {
+    let mut generator = HtmlGenerator::new(index);
+    walk(&mut generator, asts);
+    generator.site()
+}
+
+/// Converts the text of a doc comment, with HTML already escaped, into
+/// paragraphs and code blocks.
+///
+/// Blank lines separate paragraphs, text between backticks becomes inline
+/// code, and markdown fences become `<pre>` blocks.
+fn html_text(text: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = vec![];
+    let mut fence: Option<(&str, Vec<&str>)> = None;
+
+    let flush = |paragraph: &mut Vec<&str>, html: &mut String| {
+        if !paragraph.is_empty() {
+            html.push_str(format!("<p>{}</p>\n", html_inline(&paragraph.join("\n"))).as_str());
+            paragraph.clear();
+        }
+    };
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        match (&mut fence, fence_marker(line)) {
+            (Some((open, _)), _) if trimmed == *open => {
+                let (_, code) = fence.take().unwrap();
+                html.push_str(format!("{}</code></pre>\n", code.join("\n")).as_str());
+            }
+            (Some((_, code)), _) => code.push(line),
+            (None, Some(marker)) => {
+                flush(&mut paragraph, &mut html);
+                let lang = trimmed[marker.len()..].trim();
+                match lang {
+                    "" => html.push_str("<pre><code>"),
+                    _ => html.push_str(format!("<pre><code class=\"language-{}\">", lang).as_str()),
+                }
+                fence = Some((marker, vec![]));
+            }
+            (None, None) if trimmed.is_empty() => flush(&mut paragraph, &mut html),
+            (None, None) => paragraph.push(trimmed),
+        }
+    }
+
+    if let Some((_, code)) = fence {
+        html.push_str(format!("{}</code></pre>\n", code.join("\n")).as_str());
+    }
+
+    flush(&mut paragraph, &mut html);
+
+    html
+}
+
+/// Converts text between backticks into inline code.
+fn html_inline(text: &str) -> String {
+    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"`([^`]+)`").unwrap());
+    RE.replace_all(text, "<code>$1</code>").into_owned()
+}
+
+/// Generates a labelled paragraph for a single block tag.
+fn html_tag(label: &str, text: &str) -> String {
+    if text.is_empty() {
+        format!("<p><strong>{}</strong></p>\n", label)
+    } else {
+        format!("<p><strong>{}</strong>: {}</p>\n", label, html_inline(text))
+    }
+}
+
+/// Generates a labelled paragraph for a repeatable block tag, as a list if
+/// the tag was given more than once.
+fn html_tag_list(label: &str, items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => html_tag(label, item),
+        _ => {
+            let list = items
+                .iter()
+                .map(|item| format!("<li>{}</li>\n", html_inline(item)))
+                .collect::<String>();
+
+            format!("<p><strong>{}</strong>:</p>\n<ul>\n{}</ul>\n", label, list)
+        }
+    }
+}
+
+/// Generates the HTML for the description of a node, along with the block
+/// tags of its doc comment, in the same order as the markdown output.
+fn html_description(comment: Option<&entity::DocComment>) -> String {
+    let Some(c) = comment else {
+        return String::from("<p>No documentation available</p>\n");
+    };
+
+    let mut html = String::new();
+
+    if let Some(deprecated) = &c.deprecated {
+        html.push_str(&html_tag("Deprecated", deprecated).replacen(
+            "<p>",
+            "<p class=\"deprecated\">",
+            1,
+        ));
+    }
+
+    if let Some(brief) = &c.brief {
+        html.push_str(&html_tag("Brief", brief));
+    }
+
+    html.push_str(&html_text(&c.comment));
+
+    if let Some(since) = &c.since {
+        html.push_str(&html_tag("Since", since));
+    }
+
+    html.push_str(&html_tag_list("Author", &c.authors));
+    html.push_str(&html_tag_list("Precondition", &c.pre));
+    html.push_str(&html_tag_list("Postcondition", &c.post));
+    html.push_str(&html_tag_list("Throws", &c.throws));
+    html.push_str(&html_tag_list("Note", &c.notes));
+    html.push_str(&html_tag_list("Warning", &c.warnings));
+    html.push_str(&html_tag_list("See also", &c.see));
+    html.push_str(&html_tag_list("Todo", &c.todos));
+
+    html
+}
+
+/// Generates the list of documented parameters, or nothing if there are no
+/// named parameters.
+fn html_params<'b>(
+    names: impl Iterator<Item = &'b str>,
+    comment: Option<&entity::DocComment>,
+) -> String {
+    let mut html = String::new();
+
+    for name in names {
+        let item = match comment {
+            Some(c) => match c.params.iter().find(|p| p.name == name) {
+                Some(p) => match p.direction {
+                    Some(direction) => format!(
+                        "<li><code>{}</code> <em>({})</em>: {}</li>\n",
+                        name,
+                        direction,
+                        html_inline(&p.description)
+                    ),
+                    None => format!(
+                        "<li><code>{}</code>: {}</li>\n",
+                        name,
+                        html_inline(&p.description)
+                    ),
+                },
+                None => format!("<li><code>{}</code>: No description</li>\n", name),
+            },
+            None => format!("<li><code>{}</code></li>\n", name),
+        };
+
+        html.push_str(&item);
+    }
+
+    if html.is_empty() {
+        html
+    } else {
+        format!("<h3>Parameters</h3>\n<ul>\n{}</ul>\n", html)
+    }
+}
+
+/// Generates a table of the individually documented return values, or
+/// nothing if there are none.
+fn html_retvals(comment: Option<&entity::DocComment>) -> String {
+    let Some(c) = comment.filter(|c| !c.retvals.is_empty()) else {
+        return String::new();
+    };
+
+    let mut html = String::from("<table>\n<tr><th>Value</th><th>Description</th></tr>\n");
+
+    for retval in &c.retvals {
+        html.push_str(
+            format!(
+                "<tr><td><code>{}</code></td><td>{}</td></tr>\n",
+                html_escape(&retval.value),
+                html_inline(&retval.description)
+            )
+            .as_str(),
+        );
+    }
+
+    html.push_str("</table>\n");
+
+    html
+}
+
+/// Highlights keywords and literals in a run of C code that contains no
+/// links, escaping it for use in HTML.
+fn highlight_plain(code: &str) -> String {
+    static RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"(?<pp>#\s*\w+)|(?<word>\b[A-Za-z_]\w*\b)|(?<lit>\b\d[\w.]*|"(?:[^"\\]|\\.)*"|'(?:[^'\\]|\\.)*')"#).unwrap()
+    });
+
+    let mut html = String::new();
+    let mut last = 0;
+
+    for caps in RE.captures_iter(code) {
+        let m = caps.get(0).unwrap();
+        let class = if caps.name("pp").is_some() {
+            "kw"
+        } else if caps.name("lit").is_some() {
+            "lit"
+        } else if KEYWORDS.contains(&m.as_str()) {
+            "kw"
+        } else {
+            continue;
+        };
+
+        html.push_str(&html_escape(&code[last..m.start()]));
+        html.push_str(
+            format!(
+                "<span class=\"{}\">{}</span>",
+                class,
+                html_escape(m.as_str())
+            )
+            .as_str(),
+        );
+        last = m.end();
+    }
+
+    html.push_str(&html_escape(&code[last..]));
+
+    html
+}
+
+impl<'i> HtmlGenerator<'i> {
+    /// Creates a generator that links to the symbols in the given index.
+    pub fn new(index: &'i SymbolIndex) -> Self {
+        Self {
+            index,
+            modules: vec![],
+            pages: HashMap::new(),
+            symbols: vec![],
+            current: 0,
+            file: None,
+            body: String::new(),
+            out: vec![],
+        }
+    }
+
+    /// Returns the link to a section of the site, given its anchor.
+    fn href(&self, anchor: &str) -> String {
+        match self.pages.get(anchor) {
+            Some(page) => format!("{}#{}", page, anchor),
+            None => format!("#{}", anchor),
+        }
+    }
+
+    /// Formats an inline link as a link to the section of its target, or as
+    /// plain text if the target is not documented.
+    fn link(&self, link: &entity::Link) -> String {
+        let text = link.label.as_deref().unwrap_or(&link.target);
+
+        match self.index.anchor(&link.target) {
+            Some(anchor) => format!(
+                "<a href=\"{}\">{}</a>",
+                html_escape(&self.href(anchor)),
+                text
+            ),
+            None => String::from(text),
+        }
+    }
+
+    /// Highlights a C snippet, linking the names of documented types other
+    /// than `own` to their sections.
+    fn highlight(&self, code: &str, own: &str) -> String {
+        type_runs(code, self.index, own)
+            .into_iter()
+            .map(|run| match run {
+                (text, Some(anchor)) => format!(
+                    "<a class=\"type\" href=\"{}\">{}</a>",
+                    html_escape(&self.href(anchor)),
+                    html_escape(text)
+                ),
+                (text, None) => highlight_plain(text),
+            })
+            .collect()
+    }
+
+    /// Formats a C declaration as a highlighted signature block.
+    fn signature(&self, code: &str, own: &str) -> String {
+        format!(
+            "<pre class=\"signature\"><code>{}</code></pre>\n",
+            self.highlight(code, own)
+        )
+    }
+
+    /// Generates the sidebar, marking the page at `current` as the one being
+    /// viewed.
+    fn sidebar(&self, current: &str) -> String {
+        let mut html = String::from("<nav class=\"sidebar\">\n");
+        html.push_str("<input id=\"search\" type=\"search\" placeholder=\"Search...\" autocomplete=\"off\">\n");
+        html.push_str("<ul id=\"search-results\"></ul>\n");
+        html.push_str("<h2><a href=\"index.html\">Modules</a></h2>\n<ul>\n");
+
+        for module in &self.modules {
+            let class = if module.page == current {
+                " class=\"current\""
+            } else {
+                ""
+            };
+
+            html.push_str(
+                format!(
+                    "<li{}><a href=\"{}\">{}</a></li>\n",
+                    class,
+                    html_escape(&module.page),
+                    html_escape(&module.name)
+                )
+                .as_str(),
+            );
+        }
+
+        html.push_str("</ul>\n");
+
+        for kind in [
+            "Function", "Struct", "Union", "Enum", "Typedef", "Macro", "Variable",
+        ] {
+            let mut items = self
+                .symbols
+                .iter()
+                .filter(|symbol| symbol.kind == kind)
+                .map(|symbol| (&symbol.title, &symbol.href))
+                .collect::<Vec<_>>();
+
+            if items.is_empty() {
+                continue;
+            }
+
+            items.sort();
+
+            html.push_str(format!("<h3>{}s</h3>\n<ul>\n", kind).as_str());
+
+            for (title, href) in items {
+                html.push_str(
+                    format!(
+                        "<li><a href=\"{}\">{}</a></li>\n",
+                        html_escape(href),
+                        html_escape(title)
+                    )
+                    .as_str(),
+                );
+            }
+
+            html.push_str("</ul>\n");
+        }
+
+        html.push_str("</nav>\n");
+
+        html
+    }
+
+    /// Wraps the body of a page in the shared layout.
+    fn layout(&self, title: &str, current: &str, body: &str) -> String {
+        format!(
+            "<!DOCTYPE html>\n\
+             <html lang=\"en\">\n\
+             <head>\n\
+             <meta charset=\"utf-8\">\n\
+             <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+             <title>{}</title>\n\
+             <link rel=\"stylesheet\" href=\"style.css\">\n\
+             </head>\n\
+             <body>\n\
+             {}\
+             <main>\n\
+             {}\
+             </main>\n\
+             <script src=\"search-index.js\"></script>\n\
+             <script src=\"search.js\"></script>\n\
+             </body>\n\
+             </html>\n",
+            html_escape(title),
+            self.sidebar(current),
+            body
+        )
+    }
+
+    /// Generates the front page, listing the modules and what they document.
+    fn index_page(&self) -> String {
+        let mut body = String::from("<h1>Modules</h1>\n<table>\n");
+        body.push_str("<tr><th>Module</th><th>Symbols</th></tr>\n");
+
+        for module in &self.modules {
+            body.push_str(
+                format!(
+                    "<tr><td><a href=\"{}\">{}</a></td><td>{}</td></tr>\n",
+                    html_escape(&module.page),
+                    html_escape(&module.name),
+                    module.count
+                )
+                .as_str(),
+            );
+        }
+
+        body.push_str("</table>\n");
+
+        self.layout("Modules", "index.html", &body)
+    }
+
+    /// Generates the section documenting a node, followed by its source
+    /// location.
+    ///
+    /// Inline links in the node's doc comment are resolved before `body` is
+    /// called with it.
+    fn section(
+        &self,
+        node: &Node,
+        body: impl FnOnce(&Self, Option<&entity::DocComment>) -> String,
+    ) -> String {
+        let Some(value) = node.get_value() else {
+            return String::new();
+        };
+
+        let comment = node
+            .get_comment()
+            .map(|c| c.map_text(|text| replace_links(&html_escape(text), |link| self.link(link))));
+
+        let mut html = format!(
+            "<section class=\"item\" id=\"{}\">\n<h2>{} <code>{}</code></h2>\n",
+            value.anchor(),
+            value.kind(),
+            html_escape(&value.title())
+        );
+
+        html.push_str(&body(self, comment.as_ref()));
+
+        let span = node.get_span();
+
+        match self.file.as_deref() {
+            _ if span.line == 0 => {}
+            Some(file) => html.push_str(
+                format!(
+                    "<p class=\"source\">Defined in <code>{}:{}</code></p>\n",
+                    html_escape(file),
+                    span.line
+                )
+                .as_str(),
+            ),
+            None => html.push_str(
+                format!("<p class=\"source\">Defined at line {}</p>\n", span.line).as_str(),
+            ),
+        }
+
+        html.push_str("</section>\n");
+
+        html
+    }
+
+    /// Generates the body of the section documenting an enum.
+    fn enum_body(&self, node: &entity::Enum, comment: Option<&entity::DocComment>) -> String {
+        let mut html = html_description(comment);
+        html.push_str("<h3>Variants</h3>\n<table>\n");
+        html.push_str("<tr><th>Variant</th><th>Value</th><th>Description</th></tr>\n");
+
+        for variant in &node.variants {
+            let value = match &variant.value {
+                Some(value) => format!("<code>{}</code>", html_escape(value)),
+                None => String::new(),
+            };
+            let desc = variant.description.as_deref().unwrap_or("");
+
+            html.push_str(
+                format!(
+                    "<tr><td><code>{}</code></td><td>{}</td><td>{}</td></tr>\n",
+                    html_escape(&variant.name),
+                    value,
+                    html_inline(&html_escape(desc))
+                )
+                .as_str(),
+            );
+        }
+
+        html.push_str("</table>\n");
+
+        html
+    }
+
+    /// Generates the body of the section documenting a function.
+    fn function_body(
+        &self,
+        node: &entity::Function,
+        comment: Option<&entity::DocComment>,
+    ) -> String {
+        let mut html = self.signature(&function_signature(node), "");
+        html.push_str(&html_description(comment));
+
+        let retvals = html_retvals(comment);
+
+        if !node.return_type.is_void() || !retvals.is_empty() {
+            html.push_str("<h3>Returns</h3>\n");
+        }
+
+        if !node.return_type.is_void() {
+            let desc = match comment.and_then(|c| c.retval.as_ref()) {
+                Some(r) => html_inline(&r.description),
+                None => String::from("No description"),
+            };
+
+            html.push_str(
+                format!(
+                    "<p><code>{}</code>: {}</p>\n",
+                    self.highlight(&node.return_type.to_string(), ""),
+                    desc
+                )
+                .as_str(),
+            );
+        }
+
+        html.push_str(&retvals);
+
+        let names = node.params.iter().filter_map(|param| param.name.as_deref());
+        html.push_str(&html_params(names, comment));
+
+        html
+    }
+
+    /// Generates the body of the section documenting a macro.
+    fn macro_body(&self, node: &entity::Macro, comment: Option<&entity::DocComment>) -> String {
+        let signature = entity::Macro {
+            value: summarize(&node.value),
+            ..node.clone()
+        };
+
+        let mut html = self.signature(&signature.to_string(), "");
+        html.push_str(&html_description(comment));
+
+        let retval = comment.and_then(|c| c.retval.as_ref());
+        let retvals = html_retvals(comment);
+
+        if retval.is_some() || !retvals.is_empty() {
+            html.push_str("<h3>Returns</h3>\n");
+        }
+
+        if let Some(r) = retval {
+            html.push_str(format!("<p>{}</p>\n", html_inline(&r.description)).as_str());
+        }
+
+        html.push_str(&retvals);
+
+        if let Some(params) = &node.params {
+            html.push_str(&html_params(params.iter().map(String::as_str), comment));
+        }
+
+        html
+    }
+
+    /// Generates the body of the section documenting a struct or union.
+    fn members_body(
+        &self,
+        members: &[entity::Member],
+        comment: Option<&entity::DocComment>,
+    ) -> String {
+        let mut html = html_description(comment);
+        html.push_str("<h3>Members</h3>\n");
+        html.push_str(&self.members(members));
+
+        html
+    }
+
+    /// Generates a list of struct members, nesting the members of inline
+    /// struct and union definitions.
+    fn members(&self, members: &[entity::Member]) -> String {
+        let mut html = String::from("<ul>\n");
+
+        for member in members {
+            let code = format!("<code>{}</code>", self.highlight(&member.to_string(), ""));
+
+            match &member.description {
+                Some(desc) => html.push_str(
+                    format!("<li>{}: {}", code, html_inline(&html_escape(desc))).as_str(),
+                ),
+                None => html.push_str(format!("<li>{}", code).as_str()),
+            }
+
+            if let Some(aggregate) = &member.aggregate {
+                html.push('\n');
+                html.push_str(&self.members(aggregate.members()));
+            }
+
+            html.push_str("</li>\n");
+        }
+
+        html.push_str("</ul>\n");
+
+        html
+    }
+
+    /// Generates the client-side search index, with an entry for every
+    /// documented symbol.
+    fn search_index(&self) -> String {
+        let mut js = String::from("var searchIndex = [\n");
+
+        for symbol in &self.symbols {
+            js.push_str(
+                format!(
+                    "{{\"name\":\"{}\",\"kind\":\"{}\",\"module\":\"{}\",\"href\":\"{}\",\"desc\":\"{}\"}},\n",
+                    json_escape(&symbol.title),
+                    symbol.kind.to_lowercase(),
+                    json_escape(&symbol.module),
+                    json_escape(&symbol.href),
+                    json_escape(&symbol.desc)
+                )
+                .as_str(),
+            );
+        }
+
+        js.push_str("];\n");
+
+        js
+    }
+
+    /// Returns the files of the site, with the pages of the modules visited
+    /// so far.
+    fn site(&mut self) -> Vec<Page> {
+        let mut pages = vec![Page {
+            path: String::from("index.html"),
+            contents: self.index_page(),
+        }];
+
+        pages.append(&mut self.out);
+
+        pages.push(Page {
+            path: String::from("search-index.js"),
+            contents: self.search_index(),
+        });
+        pages.push(Page {
+            path: String::from("search.js"),
+            contents: String::from(SEARCH),
+        });
+        pages.push(Page {
+            path: String::from("style.css"),
+            contents: String::from(STYLE),
+        });
+
+        pages
+    }
+}
+
+impl<'i> Generator for HtmlGenerator<'i> {
+    fn begin(&mut self, asts: &[AST]) {
+        // The site's own `index.html` takes the name `index`.
+        let mut taken = HashSet::from([String::from("index")]);
+
+        for ast in asts {
+            let name = module_name(ast, &mut taken);
+            let page = format!("{}.html", name);
+            let mut count = 0;
+
+            for node in ast.get_iter() {
+                let Some(value) = node.get_value() else {
+                    continue;
+                };
+
+                self.pages
+                    .entry(value.anchor())
+                    .or_insert_with(|| page.clone());
+
+                self.symbols.push(Symbol {
+                    kind: value.kind(),
+                    title: value.title(),
+                    module: name.clone(),
+                    href: format!("{}#{}", page, value.anchor()),
+                    desc: node.get_comment().map(summary).unwrap_or_default(),
+                });
+
+                count += 1;
+            }
+
+            self.modules.push(Module { name, page, count });
+        }
+    }
+
+    fn begin_module(&mut self, ast: &AST) {
+        let module = &self.modules[self.current];
+
+        self.file = ast.get_file().map(String::from);
+        self.body = format!("<h1>Module {}</h1>\n", html_escape(&module.name));
+    }
+
+    fn visit_function(&mut self, node: &Node, function: &entity::Function) {
+        let html = self.section(node, |site, comment| site.function_body(function, comment));
+        self.body.push_str(&html);
+    }
+
+    fn visit_struct(&mut self, node: &Node, x: &entity::Struct) {
+        let html = self.section(node, |site, comment| site.members_body(&x.members, comment));
+        self.body.push_str(&html);
+    }
+
+    fn visit_union(&mut self, node: &Node, x: &entity::Union) {
+        let html = self.section(node, |site, comment| site.members_body(&x.members, comment));
+        self.body.push_str(&html);
+    }
+
+    fn visit_enum(&mut self, node: &Node, x: &entity::Enum) {
+        let html = self.section(node, |site, comment| site.enum_body(x, comment));
+        self.body.push_str(&html);
+    }
+
+    fn visit_typedef(&mut self, node: &Node, typedef: &entity::Typedef) {
+        let html = self.section(node, |site, comment| {
+            let mut html = site.signature(&typedef.to_string(), &typedef.name);
+            html.push_str(&html_description(comment));
+            html
+        });
+        self.body.push_str(&html);
+    }
+
+    fn visit_macro(&mut self, node: &Node, x: &entity::Macro) {
+        let html = self.section(node, |site, comment| site.macro_body(x, comment));
+        self.body.push_str(&html);
+    }
+
+    fn visit_variable(&mut self, node: &Node, variable: &entity::Variable) {
+        let html = self.section(node, |site, comment| {
+            let signature = entity::Variable {
+                initializer: variable.initializer.as_deref().map(summarize),
+                ..variable.clone()
+            };
+
+            let mut html = site.signature(&signature.to_string(), "");
+            html.push_str(&html_description(comment));
+            html
+        });
+        self.body.push_str(&html);
+    }
+
+    fn end_module(&mut self, _ast: &AST) {
+        let module = &self.modules[self.current];
+        let contents = self.layout(
+            format!("Module {}", module.name).as_str(),
+            &module.page,
+            &self.body,
+        );
+
+        self.out.push(Page {
+            path: module.page.clone(),
+            contents,
+        });
+        self.current += 1;
+    }
+
+    fn finish(&mut self) -> Output {
+        Output::Site(self.site())
+    }
+
+    fn default_dir(&self) -> &str {
+        "html"
+    }
+}
@@ -0,0 +1,484 @@
+//! # JSON Generation Module
+//!
+//! This module exports the API parsed from one or more source files as a
+//! single JSON document, for tools such as binding generators to consume.
+//!
+//! ## Schema
+//!
+//! The document is an object with the version of the schema it follows and
+//! the modules it describes, one per input file:
+//!
+//! ```json
+//! { "schema_version": 1, "modules": [{ "file": "list.h", "items": [] }] }
+//! ```
+//!
+//! Every item of a module has these fields, followed by fields that depend on
+//! its kind:
+//!
+//! - `kind`: one of `function`, `struct`, `union`, `enum`, `typedef`, `macro`
+//!   or `variable`.
+//! - `name`: the name of the item, which is empty for an anonymous struct,
+//!   union or enum.
+//! - `anchor`: the anchor of the item's section in generated documentation.
+//! - `location`: the `line`, `column`, `start` and `end` of the item in its
+//!   file. Lines and columns count from 1, and offsets are in bytes.
+//! - `doc`: the item's doc comment, or `null` if it has none.
+//!
+//! | Kind | Fields |
+//! | --- | --- |
+//! | `function` | `signature`, `storage`, `specifiers`, `attributes`, `return_type`, `params` |
+//! | `struct`, `union` | `alias`, `members` |
+//! | `enum` | `alias`, `variants` |
+//! | `typedef` | `signature`, `type`, `arrays`, `pointee_arrays`, `function` |
+//! | `macro` | `signature`, `params`, `value` |
+//! | `variable` | `signature`, `storage`, `type`, `arrays`, `pointee_arrays`, `function`, `initializer` |
+//!
+//! A type is an object with its `name`, the `qualifiers` of its base type,
+//! its number of `pointers`, the `pointer_qualifiers` of each pointer level,
+//! outermost last, and its `spelling` in C. Parameters and members have a
+//! `name`, which may be `null`, a `type`, their array extents as `arrays`,
+//! the extents of a pointed-to array as `pointee_arrays`, the parameters of a
+//! pointed-to `function` or `null`, and their `declaration` in C. Parameters
+//! also say whether they are `variadic`; members have a `bitfield` width, a
+//! `description` and the inline definition of their type as `aggregate`, each
+//! of which may be `null`.
+//!
+//! A doc comment has a `description`, with surrounding whitespace trimmed,
+//! a `brief`, its `params` with their `name`, `description` and `direction`
+//! (`in`, `out`, `inout` or `null`), a `return` description, `retvals` with
+//! their `value` and `description`, the lists `notes`, `warnings`, `see`,
+//! `throws`, `authors`, `todos`, `pre` and `post`, `since` and `deprecated`,
+//! its inline `links` with their `target` and `label`, and its own
+//! `location`.
+//!
+//! New fields may be added without changing the version of the schema, so
+//! consumers should ignore fields they do not know. Removing or changing a
+//! field bumps [`SCHEMA_VERSION`].
+
+use crate::ast::{Node, AST};
+use crate::diagnostics::json_escape;
+use crate::entity;
+use crate::generator::{function_signature, walk, Generator, Output};
+use crate::span::Span;
+
+/// The version of the schema the generated JSON follows.
+pub const SCHEMA_VERSION: usize = 1;
+
+/// Represents a JSON value.
+enum Value {
+    Null,
+    Bool(bool),
+    Number(usize),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(&'static str, Value)>),
+}
+
+impl Value {
+    /// Creates a string value.
+    fn string(s: &str) -> Self {
+        Value::String(String::from(s))
+    }
+
+    /// Creates a string value, or `null` if there is no string.
+    fn optional(s: Option<&str>) -> Self {
+        s.map_or(Value::Null, Value::string)
+    }
+
+    /// Creates an array of strings.
+    fn strings(items: &[String]) -> Self {
+        Value::Array(items.iter().map(|item| Value::string(item)).collect())
+    }
+
+    /// Writes this value as pretty-printed JSON, indenting nested values by
+    /// two spaces per level.
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = "  ".repeat(depth + 1);
+
+        match self {
+            Value::Null => out.push_str("null"),
+            Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+            Value::Number(n) => out.push_str(&n.to_string()),
+            Value::String(s) => out.push_str(format!("\"{}\"", json_escape(s)).as_str()),
+            Value::Array(items) if items.is_empty() => out.push_str("[]"),
+            Value::Array(items) => {
+                out.push_str("[\n");
+
+                for (i, item) in items.iter().enumerate() {
+                    out.push_str(&indent);
+                    item.write(out, depth + 1);
+                    out.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+
+                out.push_str(format!("{}]", "  ".repeat(depth)).as_str());
+            }
+            Value::Object(fields) if fields.is_empty() => out.push_str("{}"),
+            Value::Object(fields) => {
+                out.push_str("{\n");
+
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    out.push_str(format!("{}\"{}\": ", indent, key).as_str());
+                    value.write(out, depth + 1);
+                    out.push_str(if i + 1 < fields.len() { ",\n" } else { "\n" });
+                }
+
+                out.push_str(format!("{}}}", "  ".repeat(depth)).as_str());
+            }
+        }
+    }
+}
+
+/// Generates a JSON document describing the API parsed from one or more
+/// source files.
+///
+/// # Arguments
+///
+/// * `asts` - The ASTs to describe, one module per AST.
+///
+/// # Returns
+///
+/// A `String` containing the JSON document, following the schema described
+/// in the [module documentation](self).
+pub fn generate_json(asts: &[AST]) -> String {
+    let mut generator = JsonGenerator::new();
+    walk(&mut generator, asts);
+    generator.document()
+}
+
+/// Describes the location of a span.
+fn json_location(span: Span) -> Value {
+    Value::Object(vec![
+        ("line", Value::Number(span.line)),
+        ("column", Value::Number(span.column)),
+        ("start", Value::Number(span.start)),
+        ("end", Value::Number(span.end)),
+    ])
+}
+
+/// Describes a type.
+fn json_type(ty: &entity::Type) -> Value {
+    Value::Object(vec![
+        ("name", Value::string(&ty.name)),
+        ("qualifiers", Value::strings(&ty.qualifiers)),
+        ("pointers", Value::Number(ty.pointers.len())),
+        (
+            "pointer_qualifiers",
+            Value::Array(
+                ty.pointers
+                    .iter()
+                    .map(|level| Value::strings(level))
+                    .collect(),
+            ),
+        ),
+        ("spelling", Value::String(ty.to_string())),
+    ])
+}
+
+/// Describes the parameters of a pointed-to function, or `null` if there are
+/// none.
+fn json_function(function: Option<&[entity::Parameter]>) -> Value {
+    function.map_or(Value::Null, json_params)
+}
+
+/// Describes a list of function parameters.
+fn json_params(params: &[entity::Parameter]) -> Value {
+    Value::Array(
+        params
+            .iter()
+            .map(|param| {
+                Value::Object(vec![
+                    ("name", Value::optional(param.name.as_deref())),
+                    ("type", json_type(&param.ty)),
+                    ("arrays", Value::strings(&param.arrays)),
+                    ("pointee_arrays", Value::strings(&param.pointee_arrays)),
+                    ("function", json_function(param.function.as_deref())),
+                    ("variadic", Value::Bool(param.is_variadic())),
+                    ("declaration", Value::String(param.to_string())),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// Describes a list of struct or union members.
+fn json_members(members: &[entity::Member]) -> Value {
+    Value::Array(
+        members
+            .iter()
+            .map(|member| {
+                let aggregate = match member.aggregate.as_deref() {
+                    Some(entity::Aggregate::Struct(x)) => {
+                        json_aggregate("struct", &x.name, &x.members)
+                    }
+                    Some(entity::Aggregate::Union(x)) => {
+                        json_aggregate("union", &x.name, &x.members)
+                    }
+                    None => Value::Null,
+                };
+
+                Value::Object(vec![
+                    ("name", Value::optional(member.name.as_deref())),
+                    ("type", json_type(&member.ty)),
+                    ("arrays", Value::strings(&member.arrays)),
+                    ("pointee_arrays", Value::strings(&member.pointee_arrays)),
+                    ("function", json_function(member.function.as_deref())),
+                    ("bitfield", Value::optional(member.bitfield.as_deref())),
+                    (
+                        "description",
+                        Value::optional(member.description.as_deref()),
+                    ),
+                    ("aggregate", aggregate),
+                    ("declaration", Value::String(member.to_string())),
+                ])
+            })
+            .collect(),
+    )
+}
+
+/// Describes a struct or union defined inline as the type of a member.
+fn json_aggregate(kind: &str, name: &str, members: &[entity::Member]) -> Value {
+    Value::Object(vec![
+        ("kind", Value::string(kind)),
+        ("name", Value::string(name)),
+        ("members", json_members(members)),
+    ])
+}
+
+/// Describes a doc comment.
+fn json_doc(doc: &entity::DocComment) -> Value {
+    let params = doc
+        .params
+        .iter()
+        .map(|param| {
+            let direction = param.direction.map(|direction| match direction {
+                entity::Direction::In => "in",
+                entity::Direction::Out => "out",
+                entity::Direction::InOut => "inout",
+            });
+
+            Value::Object(vec![
+                ("name", Value::string(&param.name)),
+                ("description", Value::string(&param.description)),
+                ("direction", Value::optional(direction)),
+            ])
+        })
+        .collect();
+
+    let retvals = doc
+        .retvals
+        .iter()
+        .map(|retval| {
+            Value::Object(vec![
+                ("value", Value::string(&retval.value)),
+                ("description", Value::string(&retval.description)),
+            ])
+        })
+        .collect();
+
+    let links = doc
+        .links
+        .iter()
+        .map(|link| {
+            Value::Object(vec![
+                ("target", Value::string(&link.target)),
+                ("label", Value::optional(link.label.as_deref())),
+            ])
+        })
+        .collect();
+
+    Value::Object(vec![
+        ("description", Value::string(doc.comment.trim())),
+        ("brief", Value::optional(doc.brief.as_deref())),
+        ("params", Value::Array(params)),
+        (
+            "return",
+            Value::optional(doc.retval.as_ref().map(|r| r.description.as_str())),
+        ),
+        ("retvals", Value::Array(retvals)),
+        ("notes", Value::strings(&doc.notes)),
+        ("warnings", Value::strings(&doc.warnings)),
+        ("see", Value::strings(&doc.see)),
+        ("since", Value::optional(doc.since.as_deref())),
+        ("deprecated", Value::optional(doc.deprecated.as_deref())),
+        ("throws", Value::strings(&doc.throws)),
+        ("authors", Value::strings(&doc.authors)),
+        ("todos", Value::strings(&doc.todos)),
+        ("pre", Value::strings(&doc.pre)),
+        ("post", Value::strings(&doc.post)),
+        ("links", Value::Array(links)),
+        ("location", json_location(doc.span)),
+    ])
+}
+
+/// Describes the fields every item has.
+fn json_item(node: &Node) -> Vec<(&'static str, Value)> {
+    let Some(value) = node.get_value() else {
+        return vec![];
+    };
+
+    vec![
+        ("kind", Value::String(value.kind().to_lowercase())),
+        ("name", Value::string(value.name())),
+        ("anchor", Value::String(value.anchor())),
+        ("location", json_location(node.get_span())),
+        ("doc", node.get_comment().map_or(Value::Null, json_doc)),
+    ]
+}
+
+/// Generates a JSON document describing the API, following the schema
+/// described in the [module documentation](self).
+#[derive(Default)]
+pub struct JsonGenerator {
+    /// The modules visited so far.
+    modules: Vec<Value>,
+
+    /// The items of the module being visited.
+    items: Vec<Value>,
+}
+
+impl JsonGenerator {
+    /// Creates a new generator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an item to the module being visited, with the fields every item
+    /// has followed by `fields`.
+    fn item<const N: usize>(&mut self, node: &Node, fields: [(&'static str, Value); N]) {
+        let mut item = json_item(node);
+        item.extend(fields);
+        self.items.push(Value::Object(item));
+    }
+
+    /// Returns the JSON document describing the modules visited so far.
+    fn document(&mut self) -> String {
+        let document = Value::Object(vec![
+            ("schema_version", Value::Number(SCHEMA_VERSION)),
+            ("modules", Value::Array(std::mem::take(&mut self.modules))),
+        ]);
+
+        let mut json = String::new();
+        document.write(&mut json, 0);
+        json.push('\n');
+
+        json
+    }
+}
+
+impl Generator for JsonGenerator {
+    fn begin_module(&mut self, _ast: &AST) {
+        self.items.clear();
+    }
+
+    fn visit_function(&mut self, node: &Node, x: &entity::Function) {
+        self.item(
+            node,
+            [
+                ("signature", Value::String(function_signature(x))),
+                ("storage", Value::optional(x.storage.as_deref())),
+                ("specifiers", Value::strings(&x.specifiers)),
+                ("attributes", Value::strings(&x.attributes)),
+                ("return_type", json_type(&x.return_type)),
+                ("params", json_params(&x.params)),
+            ],
+        );
+    }
+
+    fn visit_struct(&mut self, node: &Node, x: &entity::Struct) {
+        self.item(
+            node,
+            [
+                ("alias", Value::optional(x.alias.as_deref())),
+                ("members", json_members(&x.members)),
+            ],
+        );
+    }
+
+    fn visit_union(&mut self, node: &Node, x: &entity::Union) {
+        self.item(
+            node,
+            [
+                ("alias", Value::optional(x.alias.as_deref())),
+                ("members", json_members(&x.members)),
+            ],
+        );
+    }
+
+    fn visit_enum(&mut self, node: &Node, x: &entity::Enum) {
+        let variants = x
+            .variants
+            .iter()
+            .map(|variant| {
+                Value::Object(vec![
+                    ("name", Value::string(&variant.name)),
+                    ("value", Value::optional(variant.value.as_deref())),
+                    (
+                        "description",
+                        Value::optional(variant.description.as_deref()),
+                    ),
+                ])
+            })
+            .collect();
+
+        self.item(
+            node,
+            [
+                ("alias", Value::optional(x.alias.as_deref())),
+                ("variants", Value::Array(variants)),
+            ],
+        );
+    }
+
+    fn visit_typedef(&mut self, node: &Node, x: &entity::Typedef) {
+        self.item(
+            node,
+            [
+                ("signature", Value::String(x.to_string())),
+                ("type", json_type(&x.ty)),
+                ("arrays", Value::strings(&x.arrays)),
+                ("pointee_arrays", Value::strings(&x.pointee_arrays)),
+                ("function", json_function(x.function.as_deref())),
+            ],
+        );
+    }
+
+    fn visit_macro(&mut self, node: &Node, x: &entity::Macro) {
+        self.item(
+            node,
+            [
+                ("signature", Value::String(x.to_string())),
+                (
+                    "params",
+                    x.params.as_deref().map_or(Value::Null, Value::strings),
+                ),
+                ("value", Value::string(&x.value)),
+            ],
+        );
+    }
+
+    fn visit_variable(&mut self, node: &Node, x: &entity::Variable) {
+        self.item(
+            node,
+            [
+                ("signature", Value::String(x.to_string())),
+                ("storage", Value::optional(x.storage.as_deref())),
+                ("type", json_type(&x.ty)),
+                ("arrays", Value::strings(&x.arrays)),
+                ("pointee_arrays", Value::strings(&x.pointee_arrays)),
+                ("function", json_function(x.function.as_deref())),
+                ("initializer", Value::optional(x.initializer.as_deref())),
+            ],
+        );
+    }
+
+    fn end_module(&mut self, ast: &AST) {
+        self.modules.push(Value::Object(vec![
+            ("file", Value::optional(ast.get_file())),
+            ("items", Value::Array(std::mem::take(&mut self.items))),
+        ]));
+    }
+
+    fn finish(&mut self) -> Output {
+        Output::Document(self.document())
+    }
+}
@@ -0,0 +1,504 @@
+//! # Lexer Module
+//!
+//! This module provides a character-level lexer for C-style source code. It
+//! understands comments, string and character literals, preprocessor
+//! conditionals and brace nesting, and splits the source into doc comments
+//! and complete top-level declarations for the tokenizer to classify.
+
+use crate::span::{LineIndex, Span};
+
+/// Represents the kinds of lexemes produced by the lexer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LexemeKind {
+    /// A top-level `/** ... */` documentation comment.
+    DocComment,
+
+    /// A complete top-level declaration or definition.
+    Declaration,
+
+    /// A top-level `#define` directive, with continuation lines joined.
+    Directive,
+}
+
+/// Represents a lexeme and its source text.
+#[derive(Debug)]
+pub struct Lexeme {
+    /// The kind of the lexeme.
+    pub kind: LexemeKind,
+
+    /// The text of the lexeme, with ordinary comments replaced by whitespace.
+    pub text: String,
+
+    /// The location of the lexeme in the source.
+    pub span: Span,
+}
+
+/// Tracks the state of a single `#if` ... `#endif` block.
+#[derive(Debug)]
+struct Conditional {
+    /// Whether the enclosing block is active.
+    parent_active: bool,
+
+    /// Whether a branch of this block is known to have been taken.
+    taken: bool,
+
+    /// Whether the current branch is active.
+    active: bool,
+}
+
+/// A character-level lexer over C-style source code.
+///
+/// The lexer yields top-level doc comments and declarations in source order.
+/// Comments, string and character literals never produce lexemes on their
+/// own, and code inside `#if 0` blocks is skipped entirely.
+#[derive(Debug)]
+pub struct Lexer<'a> {
+    /// The source being lexed.
+    src: &'a str,
+
+    /// The line index of the source, used to compute spans.
+    index: LineIndex<'a>,
+
+    /// The current byte offset into the source.
+    pos: usize,
+
+    /// The byte offset of the start of the declaration being accumulated.
+    start: usize,
+
+    /// Whether only whitespace has been seen since the last newline.
+    line_start: bool,
+
+    /// The current brace nesting depth.
+    depth: usize,
+
+    /// The number of open `extern "C" {` blocks.
+    linkage: usize,
+
+    /// Whether the current top-level brace block is a function body.
+    in_body: bool,
+
+    /// The stack of open preprocessor conditionals.
+    conditionals: Vec<Conditional>,
+
+    /// The macro tested by the preceding `#ifndef`, which may be an include guard.
+    guard: Option<String>,
+
+    /// The text of the declaration being accumulated.
+    buf: String,
+}
+
+impl<'a> Lexer<'a> {
+    /// Creates a new lexer over the given source.
+    pub fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            index: LineIndex::new(src),
+            pos: 0,
+            start: 0,
+            line_start: true,
+            depth: 0,
+            linkage: 0,
+            in_body: false,
+            conditionals: vec![],
+            guard: None,
+            buf: String::new(),
+        }
+    }
+
+    /// Returns the unconsumed remainder of the source.
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    /// Returns the next character without consuming it.
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Consumes and returns the next character.
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        self.line_start = c == '\n' || (self.line_start && c.is_whitespace());
+        Some(c)
+    }
+
+    /// Appends text to the current declaration, unless inside a function body.
+    ///
+    /// Function bodies are skipped, so a definition is emitted as its head
+    /// followed by an empty `{}`.
+    fn push_str(&mut self, text: &str) {
+        if !self.in_body || self.depth == 0 {
+            self.buf.push_str(text);
+        }
+    }
+
+    /// Consumes characters up to the end of the line, excluding the newline.
+    fn skip_line(&mut self) {
+        while !matches!(self.peek(), None | Some('\n')) {
+            self.bump();
+        }
+    }
+
+    /// Returns whether code at the current position is compiled in.
+    fn is_active(&self) -> bool {
+        self.conditionals.last().map_or(true, |c| c.active)
+    }
+
+    /// Reads a block comment, including its delimiters.
+    fn read_block_comment(&mut self) -> &'a str {
+        let start = self.pos;
+        self.pos += 2;
+
+        self.pos = match self.rest().find("*/") {
+            Some(end) => self.pos + end + 2,
+            None => self.src.len(),
+        };
+        self.line_start = false;
+
+        &self.src[start..self.pos]
+    }
+
+    /// Reads a string or character literal, including its quotes.
+    fn read_literal(&mut self, quote: char) -> &'a str {
+        let start = self.pos;
+        self.bump();
+
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '\n' => break,
+                _ if c == quote => break,
+                _ => {}
+            }
+        }
+
+        &self.src[start..self.pos]
+    }
+
+    /// Reads a preprocessor directive, joining continuation lines.
+    fn read_directive(&mut self) -> String {
+        let mut directive = String::new();
+
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+
+            self.bump();
+
+            if c == '\\' && self.rest().trim_start_matches('\r').starts_with('\n') {
+                self.skip_line();
+                self.bump();
+                directive.push(' ');
+            } else {
+                directive.push(c);
+            }
+        }
+
+        directive
+    }
+
+    /// Handles a preprocessor directive starting at byte offset `start`,
+    /// updating the conditional stack.
+    ///
+    /// Returns a lexeme for a top-level `#define`, unless it defines the
+    /// include guard tested by the directive just before it.
+    fn handle_directive(&mut self, directive: &str, start: usize) -> Option<Lexeme> {
+        let directive = strip_comments(directive.trim_start_matches('#'));
+        let directive = directive.trim();
+        let split = directive
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(directive.len());
+        let (name, cond) = directive.split_at(split);
+        let guard = self.guard.take();
+
+        let parent_active = self.is_active();
+
+        match name {
+            "if" | "ifdef" | "ifndef" => {
+                let value = if name == "if" { evaluate(cond) } else { None };
+
+                self.conditionals.push(Conditional {
+                    parent_active,
+                    taken: value == Some(true),
+                    active: parent_active && value != Some(false),
+                });
+            }
+            "elif" | "elifdef" | "elifndef" => {
+                if let Some(top) = self.conditionals.last_mut() {
+                    let value = if name == "elif" { evaluate(cond) } else { None };
+
+                    top.active = top.parent_active && !top.taken && value != Some(false);
+                    top.taken |= value == Some(true);
+                }
+            }
+            "else" => {
+                if let Some(top) = self.conditionals.last_mut() {
+                    top.active = top.parent_active && !top.taken;
+                }
+            }
+            "endif" => {
+                self.conditionals.pop();
+            }
+            "define" if self.is_active() && self.depth == 0 => {
+                if guard.as_deref() == Some(cond.trim()) {
+                    return None;
+                }
+
+                return Some(Lexeme {
+                    kind: LexemeKind::Directive,
+                    text: format!("#{}", directive),
+                    span: self.index.span(start, self.pos),
+                });
+            }
+            _ => {}
+        }
+
+        if name == "ifndef" {
+            self.guard = Some(String::from(cond.trim()));
+        }
+
+        None
+    }
+
+    /// Takes the accumulated declaration as a lexeme, if it is not empty.
+    fn take_declaration(&mut self) -> Option<Lexeme> {
+        let text = self.buf.trim().to_string();
+        self.buf.clear();
+
+        if text.is_empty() || text == ";" {
+            None
+        } else {
+            Some(Lexeme {
+                kind: LexemeKind::Declaration,
+                text,
+                span: self.index.span(self.start, self.pos),
+            })
+        }
+    }
+
+    /// Handles an opening brace.
+    fn open_brace(&mut self) {
+        if self.depth == 0 {
+            let head = self.buf.trim();
+
+            if is_linkage_block(head) {
+                self.linkage += 1;
+                self.buf.clear();
+                return;
+            }
+
+            // `struct __attribute__((packed)) {` ends in a parenthesis too,
+            // but opens a struct body, not a function body.
+            self.in_body = strip_trailing_attributes(head).ends_with(')');
+        }
+
+        self.push_str("{");
+        self.depth += 1;
+    }
+
+    /// Handles a closing brace, returning a lexeme if it ends a function body.
+    fn close_brace(&mut self) -> Option<Lexeme> {
+        if self.depth == 0 {
+            if self.linkage > 0 {
+                self.linkage -= 1;
+                self.buf.clear();
+            }
+
+            return None;
+        }
+
+        self.depth -= 1;
+        self.push_str("}");
+
+        if self.depth == 0 && self.in_body {
+            self.in_body = false;
+            return self.take_declaration();
+        }
+
+        None
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Lexeme;
+
+    fn next(&mut self) -> Option<Lexeme> {
+        loop {
+            let c = self.peek()?;
+
+            if self.line_start && c == '#' {
+                let start = self.pos;
+                let directive = self.read_directive();
+
+                if let Some(lexeme) = self.handle_directive(&directive, start) {
+                    return Some(lexeme);
+                }
+
+                continue;
+            }
+
+            if !self.is_active() {
+                if c.is_whitespace() {
+                    self.bump();
+                } else {
+                    self.skip_line();
+                }
+                continue;
+            }
+
+            if self.rest().starts_with("//") {
+                self.skip_line();
+                self.push_str(" ");
+                continue;
+            }
+
+            if self.rest().starts_with("/*") {
+                let start = self.pos;
+                let comment = self.read_block_comment();
+
+                if !is_doc_comment(comment) {
+                    self.push_str(" ");
+                } else if self.depth > 0 {
+                    self.push_str(comment);
+                } else if !comment.starts_with("/**<") {
+                    return Some(Lexeme {
+                        kind: LexemeKind::DocComment,
+                        text: comment.to_string(),
+                        span: self.index.span(start, self.pos),
+                    });
+                }
+
+                continue;
+            }
+
+            if self.depth == 0 && self.buf.trim().is_empty() && !c.is_whitespace() {
+                self.start = self.pos;
+            }
+
+            match c {
+                '"' | '\'' => {
+                    let literal = self.read_literal(c);
+                    self.push_str(literal);
+                }
+                '{' => {
+                    self.bump();
+                    self.open_brace();
+                }
+                '}' => {
+                    self.bump();
+                    if let Some(lexeme) = self.close_brace() {
+                        return Some(lexeme);
+                    }
+                }
+                ';' => {
+                    self.bump();
+                    self.push_str(";");
+
+                    if self.depth == 0 {
+                        if let Some(lexeme) = self.take_declaration() {
+                            return Some(lexeme);
+                        }
+                    }
+                }
+                _ => {
+                    self.bump();
+                    self.push_str(c.encode_utf8(&mut [0; 4]));
+                }
+            }
+        }
+    }
+}
+
+/// Returns whether a block comment is a `/** ... */` doc comment.
+fn is_doc_comment(comment: &str) -> bool {
+    comment.starts_with("/**") && !comment.starts_with("/**/")
+}
+
+/// Returns whether a declaration head opens an `extern "C" {` block.
+fn is_linkage_block(head: &str) -> bool {
+    head.strip_prefix("extern")
+        .map(str::trim_start)
+        .map_or(false, |rest| rest.starts_with('"'))
+}
+
+/// Removes the `__attribute__((...))` and `[[...]]` attributes at the end of
+/// a declaration head.
+fn strip_trailing_attributes(head: &str) -> &str {
+    let mut head = head.trim_end();
+
+    loop {
+        if let Some(rest) = head.strip_suffix("]]") {
+            match rest.rfind("[[") {
+                Some(open) => head = rest[..open].trim_end(),
+                None => return head,
+            }
+        } else if head.ends_with(')') {
+            let mut depth = 0;
+            let open = head.char_indices().rev().find_map(|(i, c)| {
+                match c {
+                    ')' => depth += 1,
+                    '(' => depth -= 1,
+                    _ => {}
+                }
+                (depth == 0).then_some(i)
+            });
+
+            match open.and_then(|open| head[..open].trim_end().strip_suffix("__attribute__")) {
+                Some(rest) => head = rest.trim_end(),
+                None => return head,
+            }
+        } else {
+            return head;
+        }
+    }
+}
+
+/// Removes line and block comments from a preprocessor directive, leaving
+/// string and character literals intact.
+fn strip_comments(directive: &str) -> String {
+    let mut out = String::new();
+    let mut rest = directive;
+    let mut quote = None;
+
+    while let Some(c) = rest.chars().next() {
+        if quote.is_none() && rest.starts_with("//") {
+            break;
+        }
+
+        if quote.is_none() && rest.starts_with("/*") {
+            rest = rest[2..].find("*/").map_or("", |end| &rest[end + 4..]);
+            out.push(' ');
+            continue;
+        }
+
+        out.push(c);
+        rest = &rest[c.len_utf8()..];
+
+        match c {
+            '\\' if quote.is_some() => {
+                if let Some(escaped) = rest.chars().next() {
+                    out.push(escaped);
+                    rest = &rest[escaped.len_utf8()..];
+                }
+            }
+            '"' | '\'' if quote.is_none() => quote = Some(c),
+            _ if quote == Some(c) => quote = None,
+            _ => {}
+        }
+    }
+
+    out
+}
+
+/// Evaluates a constant preprocessor condition, if it is trivially known.
+fn evaluate(cond: &str) -> Option<bool> {
+    match cond.trim() {
+        "0" => Some(false),
+        "1" => Some(true),
+        _ => None,
+    }
+}
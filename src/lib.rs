@@ -1,5 +1,14 @@
 pub mod ast;
+pub mod diagnostics;
 pub mod entity;
+pub mod error;
+pub mod generator;
+pub mod html_gen;
+pub mod json_gen;
+pub mod lexer;
+pub mod man_gen;
 pub mod md_gen;
 pub mod parser;
+pub mod span;
+pub mod template;
 pub mod token;
@@ -8,100 +8,318 @@ use std::io::stdout;
 use std::io::BufWriter;
 use std::io::Write;
 use std::path::Path;
+use std::process::ExitCode;
 
 use doc_buildr::*;
 
-/// Builds documentation for a given input file.
+/// The format documentation is generated in if none is given.
+const DEFAULT_FORMAT: &str = "markdown";
+
+/// Represents the options given on the command line.
+#[derive(Debug, Default)]
+struct Options {
+    /// The paths of the files to document.
+    inputs: Vec<String>,
+
+    /// The path of the file to write documentation to, or stdout if unset.
+    /// For formats that generate a site, such as HTML, the path of the
+    /// directory to write the site to.
+    output: Option<String>,
+
+    /// The name of the format to generate documentation in, or markdown if
+    /// unset.
+    format: Option<String>,
+
+    /// The directory of templates that override the built-in markdown
+    /// templates, if set.
+    templates: Option<String>,
+
+    /// The format diagnostics are reported in.
+    message_format: diagnostics::MessageFormat,
+
+    /// Whether to highlight diagnostics with ANSI colors.
+    color: bool,
+}
+
+impl Options {
+    /// Parses options from command-line arguments, excluding the program name.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the parsed options if successful, or an error
+    /// message as a `String` if an argument is not recognized.
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.peekable();
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-o" | "--output" => match args.next() {
+                    Some(output) => options.output = Some(output),
+                    None => return Err(format!("Missing file after '{}'", arg)),
+                },
+                "--format" => match args.next() {
+                    Some(format) => options.format = Some(format),
+                    None => return Err(format!("Missing format after '{}'", arg)),
+                },
+                "--templates" => match args.next() {
+                    Some(dir) => options.templates = Some(dir),
+                    None => return Err(format!("Missing directory after '{}'", arg)),
+                },
+                "--color" | "--color=always" => options.color = true,
+                "--color=never" => options.color = false,
+                "--message-format" => match args.next() {
+                    Some(format) => options.message_format = message_format(&format)?,
+                    None => return Err(format!("Missing format after '{}'", arg)),
+                },
+                _ if arg.starts_with("--format=") => {
+                    options.format = Some(String::from(&arg["--format=".len()..]))
+                }
+                _ if arg.starts_with("--templates=") => {
+                    options.templates = Some(String::from(&arg["--templates=".len()..]))
+                }
+                _ if arg.starts_with("--message-format=") => {
+                    options.message_format = message_format(&arg["--message-format=".len()..])?
+                }
+                _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+                _ => options.inputs.push(arg),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+/// Parses the value of `--message-format`.
+fn message_format(value: &str) -> Result<diagnostics::MessageFormat, String> {
+    match value {
+        "human" => Ok(diagnostics::MessageFormat::Human),
+        "json" => Ok(diagnostics::MessageFormat::Json),
+        _ => Err(format!(
+            "Unknown message format '{}', expected one of: human, json",
+            value
+        )),
+    }
+}
+
+/// Represents an input file and the tokens parsed from it.
+struct Source {
+    /// The path of the file.
+    file: String,
+
+    /// The contents of the file.
+    data: String,
+
+    /// The tokens parsed from the file.
+    parsed: Vec<parser::ParsedToken>,
+
+    /// Whether a doc comment, or a declaration with one, could not be parsed.
+    failed: bool,
+}
+
+/// Reports a diagnostic about a source file to stderr.
+fn report(diagnostic: &diagnostics::Diagnostic, source: &Source, options: &Options) {
+    eprintln!(
+        "{}",
+        diagnostic.render(
+            &source.file,
+            &source.data,
+            options.message_format,
+            options.color
+        )
+    );
+}
+
+/// Reads and parses a given input file, reporting any problems found along
+/// the way to stderr.
 ///
 /// # Arguments
 ///
 /// * `input_file` - The path to the input file to process.
+/// * `options` - The options controlling how problems are reported.
 ///
 /// # Returns
 ///
-/// A `Result` containing the generated markdown as a `String` if successful,
-/// or an error message as a `String` if an error occurred.
-fn build_docs(input_file: &str) -> Result<String, String> {
+/// A `Result` containing the parsed file if successful, or an error message
+/// as a `String` if an error occurred.
+fn load(input_file: &str, options: &Options) -> Result<Source, String> {
     let Ok(data) = fs::read_to_string(input_file) else {
         return Err(format!("File '{}' not found", &input_file));
     };
 
-    let filename = Path::new(input_file).file_stem().unwrap().to_str().unwrap();
-
     let tokens = token::Token::tokenize(&data);
-    let parsed = parser::parse_tokens(&tokens);
-    let ast = ast::AST::build_ast(&parsed);
-    let md = md_gen::generate_md(&ast);
+    let (parsed, errors) = parser::parse_tokens(&tokens);
 
-    let mut docs = String::from(format!("# Module {}\n\n", filename));
-    docs.push_str(&md);
+    let source = Source {
+        file: String::from(input_file),
+        data,
+        parsed,
+        failed: errors.iter().any(|error| error.documented),
+    };
 
-    Ok(docs)
+    for error in &errors {
+        report(&diagnostics::Diagnostic::from(error), &source, options);
+    }
+
+    Ok(source)
 }
 
-/// The main entry point for the doc-buildr application.
+/// Writes the files of a generated site to a directory, creating it if
+/// needed.
 ///
-/// This function parses command-line arguments, processes input files,
-/// and writes the generated documentation to the specified output or stdout.
-fn main() {
-    enum ArgType {
-        InputFile,
-        OutputFile,
+/// # Returns
+///
+/// A `Result` that is `Ok` if every file was written, or an error message as
+/// a `String` naming the file that could not be written.
+fn write_site(dir: &str, pages: Vec<generator::Page>) -> Result<(), String> {
+    let dir = Path::new(dir);
+
+    fs::create_dir_all(dir).map_err(|_| format!("Failed to create directory {}", dir.display()))?;
+
+    for page in pages {
+        let path = dir.join(&page.path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|_| format!("Failed to create directory {}", parent.display()))?;
+        }
+
+        fs::write(&path, page.contents)
+            .map_err(|_| format!("Failed to write to file {}", path.display()))?;
     }
-    use ArgType::*;
 
-    let (inputs, output, _) = std::env::args().skip(1).fold(
-        (None::<Vec<String>>, None::<String>, InputFile),
-        |(mut inputs, mut output, mut mode), arg| {
-            match arg.as_str() {
-                "-o" | "--output" => mode = OutputFile,
-                _ => match mode {
-                    InputFile => {
-                        if let Some(ref mut files) = inputs {
-                            files.push(arg);
-                        } else {
-                            inputs = Some(vec![arg]);
-                        }
-                    }
-                    OutputFile => {
-                        output = Some(arg);
-                        mode = InputFile;
-                    }
-                },
-            };
+    Ok(())
+}
 
-            (inputs, output, mode)
-        },
-    );
+/// Writes a generated document to a file, or to stdout if no file is given.
+///
+/// # Returns
+///
+/// A `Result` that is `Ok` if the document was written, or an error message
+/// as a `String` naming the file that could not be written.
+fn write_document(output: Option<&str>, docs: &str) -> Result<(), String> {
+    let output_file = output.unwrap_or("stdout");
+    let failed = |_| format!("Failed to write to file {}", output_file);
 
-    let Some(inputs) = inputs else {
-        println!("No input files!");
-        return;
+    let mut writer: Box<dyn Write> = match output {
+        Some(x) => Box::new(BufWriter::new(
+            fs::File::create(Path::new(x)).map_err(failed)?,
+        )),
+        None => Box::new(BufWriter::new(stdout())),
     };
 
-    let output_file: &str;
+    writer.write_all(docs.as_bytes()).map_err(failed)?;
+    writer.flush().map_err(failed)
+}
 
-    let mut writer: Box<dyn Write> = Box::new(BufWriter::new(match output {
-        Some(ref x) => {
-            output_file = x.as_str();
-            Box::new(fs::File::create(&Path::new(x)).unwrap()) as Box<dyn Write>
-        }
-        None => {
-            output_file = "stdout";
-            Box::new(stdout()) as Box<dyn Write>
+/// The main entry point for the doc-buildr application.
+///
+/// This function parses command-line arguments, processes input files,
+/// and writes the generated documentation to the specified output or stdout.
+///
+/// The exit status is nonzero if any error was reported, including errors in
+/// the input files that did not stop the documentation from being written.
+fn main() -> ExitCode {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("{}", msg);
+            return ExitCode::FAILURE;
         }
-    }));
-
-    inputs.iter().fold((), |_, file| {
-        match build_docs(file) {
-            Ok(docs) => {
-                writer
-                    .write(docs.as_bytes())
-                    .expect(format!("Failed to write to file {}", output_file).as_str());
+    };
+
+    let mut registry = generator::Registry::with_builtins();
+
+    if let Some(dir) = &options.templates {
+        let templates = match template::Templates::from_dir(Path::new(dir)) {
+            Ok(templates) => templates,
+            Err(err) => {
+                eprintln!("Invalid template {}", err);
+                return ExitCode::FAILURE;
             }
+        };
+
+        registry.register("markdown", move |index| {
+            Box::new(md_gen::MarkdownGenerator::with_templates(
+                index,
+                templates.clone(),
+            ))
+        });
+    }
+
+    let format = options.format.as_deref().unwrap_or(DEFAULT_FORMAT);
+
+    if !registry.names().any(|name| name == format) {
+        let names = registry.names().collect::<Vec<_>>();
+        eprintln!(
+            "Unknown format '{}', expected one of: {}",
+            format,
+            names.join(", ")
+        );
+        return ExitCode::FAILURE;
+    }
+
+    if options.inputs.is_empty() {
+        eprintln!("No input files!");
+        return ExitCode::FAILURE;
+    }
+
+    let mut failed = false;
+    let sources = options
+        .inputs
+        .iter()
+        .filter_map(|file| match load(file, &options) {
+            Ok(source) => Some(source),
             Err(msg) => {
                 eprintln!("{}", msg);
+                failed = true;
+                None
             }
-        };
-    })
+        })
+        .collect::<Vec<_>>();
+    failed |= sources.iter().any(|source| source.failed);
+
+    let asts = sources
+        .iter()
+        .map(|source| {
+            let mut ast = ast::AST::build_ast(&source.parsed);
+            ast.set_file(&source.file);
+            ast
+        })
+        .collect::<Vec<_>>();
+
+    // Links may refer to symbols documented in any of the input files.
+    let mut index = ast::SymbolIndex::new();
+    for ast in &asts {
+        index.add(ast);
+    }
+
+    for (source, ast) in sources.iter().zip(&asts) {
+        for warning in ast.resolve_links(&index) {
+            report(&warning, source, &options);
+        }
+    }
+
+    let Some(mut generator) = registry.create(format, &index) else {
+        return ExitCode::FAILURE;
+    };
+
+    let result = match generator::generate(generator.as_mut(), &asts) {
+        generator::Output::Document(docs) => write_document(options.output.as_deref(), &docs),
+        generator::Output::Site(pages) => write_site(
+            options.output.as_deref().unwrap_or(generator.default_dir()),
+            pages,
+        ),
+    };
+
+    if let Err(msg) = result {
+        eprintln!("{}", msg);
+        failed = true;
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
 }
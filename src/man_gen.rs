@@ -0,0 +1,497 @@
+//! # Man Page Generation Module
+//!
+//! This module is responsible for generating Unix manual pages, written in
+//! roff, from the Abstract Syntax Trees (ASTs) of one or more source files.
+//! Every documented function gets a section 3 page of its own, and every
+//! module an overview page listing what it declares, all in a `man3`
+//! directory so the output can be added to `MANPATH` as is.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::ast::{Node, NodeTypes, SymbolIndex, AST};
+use crate::entity;
+use crate::generator::{
+    fence_marker, function_signature, module_name, summary, walk, Generator, Output, Page,
+};
+use crate::parser::replace_links;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+/// The manual pages are part of, as shown in their header and footer.
+const MANUAL: &str = "Library Functions Manual";
+
+/// Represents a declaration listed on the overview page of a module.
+struct Entry {
+    /// The heading of the group the declaration is listed under.
+    group: &'static str,
+
+    /// The title of the declaration.
+    title: String,
+
+    /// The summary of its doc comment.
+    desc: String,
+}
+
+/// The headings of the groups declarations are listed under on the overview
+/// page of a module, in order.
+const GROUPS: &[&str] = &["Functions", "Types", "Macros", "Variables"];
+
+/// Generates manual pages, with a page per function and an overview page per
+/// module.
+///
+/// The generated files are:
+///
+/// - `man3/<function>.3` for every function. A function declared in more
+///   than one module only gets a page for its first declaration.
+/// - `man3/<module>.3` for every module, named after the stem of its file.
+///   Modules named like a function or another module get a numeric suffix.
+pub struct ManGenerator<'i> {
+    /// The symbols that inline links may refer to.
+    index: &'i SymbolIndex,
+
+    /// The names of the functions documented in any module.
+    functions: HashSet<String>,
+
+    /// The page names of the modules, in the order they are visited.
+    modules: Vec<String>,
+
+    /// The index of the module being visited.
+    current: usize,
+
+    /// The path of the file of the module being visited, if set.
+    file: Option<String>,
+
+    /// The declarations of the module being visited.
+    entries: Vec<Entry>,
+
+    /// The pages generated so far.
+    out: Vec<Page>,
+}
+
+/// Generates manual pages from the ASTs of one or more source files.
+///
+/// # Arguments
+///
+/// * `asts` - The ASTs to document, one overview page per AST.
+/// * `index` - The symbols that inline links may refer to.
+///
+/// # Returns
+///
+/// The files of the pages, as described in [`ManGenerator`].
+pub fn generate_man(asts: &[AST], index: &SymbolIndex) -> Vec<Page> {
+    let mut generator = ManGenerator::new(index);
+    walk(&mut generator, asts);
+    std::mem::take(&mut generator.out)
+}
+
+/// Escapes text for use in roff, including in quoted macro arguments.
+fn roff_escape(text: &str) -> String {
+    text.replace('\\', "\\e")
+        .replace('-', "\\-")
+        .replace('"', "\\(dq")
+}
+
+/// Writes a line of text, escaping a leading `.` or `'` so it is not taken
+/// for a request.
+fn roff_line(line: &str) -> String {
+    match line.chars().next() {
+        Some('.' | '\'') => format!("\\&{}\n", line),
+        _ => format!("{}\n", line),
+    }
+}
+
+/// Escapes text, and converts text between backticks into bold text.
+fn roff_inline(text: &str) -> String {
+    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"`([^`]+)`").unwrap());
+
+    let mut roff = String::new();
+    let mut last = 0;
+
+    for m in RE.captures_iter(text) {
+        let (all, code) = (m.get(0).unwrap(), m.get(1).unwrap());
+        roff.push_str(&roff_escape(&text[last..all.start()]));
+        roff.push_str(format!("\\fB{}\\fR", roff_escape(code.as_str())).as_str());
+        last = all.end();
+    }
+
+    roff.push_str(&roff_escape(&text[last..]));
+
+    roff
+}
+
+/// Converts the text of a doc comment into paragraphs and code blocks.
+///
+/// Blank lines separate paragraphs, text between backticks becomes bold, and
+/// markdown fences become unfilled blocks.
+fn roff_text(text: &str) -> String {
+    let mut roff = String::new();
+    let mut paragraph: Vec<&str> = vec![];
+    let mut fence: Option<&str> = None;
+
+    let flush = |paragraph: &mut Vec<&str>, roff: &mut String| {
+        if !paragraph.is_empty() {
+            roff.push_str(".PP\n");
+
+            for line in paragraph.drain(..) {
+                roff.push_str(&roff_line(&roff_inline(line)));
+            }
+        }
+    };
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        match (fence, fence_marker(line)) {
+            (Some(open), _) if trimmed == open => {
+                roff.push_str(".fi\n");
+                fence = None;
+            }
+            (Some(_), _) => roff.push_str(&roff_line(&roff_escape(line.trim_end()))),
+            (None, Some(marker)) => {
+                flush(&mut paragraph, &mut roff);
+                roff.push_str(".PP\n.nf\n");
+                fence = Some(marker);
+            }
+            (None, None) if trimmed.is_empty() => flush(&mut paragraph, &mut roff),
+            (None, None) => paragraph.push(trimmed),
+        }
+    }
+
+    if fence.is_some() {
+        roff.push_str(".fi\n");
+    }
+
+    flush(&mut paragraph, &mut roff);
+
+    roff
+}
+
+/// Generates the header of a page.
+fn roff_header(name: &str, source: &str) -> String {
+    format!(
+        ".TH \"{}\" 3 \"\" \"{}\" \"{}\"\n",
+        roff_escape(&name.to_uppercase()),
+        roff_escape(source),
+        MANUAL
+    )
+}
+
+/// Generates the `NAME` section of a page, which `whatis` and `apropos`
+/// search.
+fn roff_name(name: &str, desc: &str) -> String {
+    match desc {
+        "" => format!(".SH NAME\n{}\n", roff_escape(name)),
+        _ => format!(
+            ".SH NAME\n{} \\- {}\n",
+            roff_escape(name),
+            roff_inline(desc)
+        ),
+    }
+}
+
+/// Generates a cross-reference to a page in section 3.
+fn roff_xref(name: &str) -> String {
+    format!(".BR \"{}\" (3)", roff_escape(name))
+}
+
+impl<'i> ManGenerator<'i> {
+    /// Creates a generator that links to the symbols in the given index.
+    pub fn new(index: &'i SymbolIndex) -> Self {
+        Self {
+            index,
+            functions: HashSet::new(),
+            modules: vec![],
+            current: 0,
+            file: None,
+            entries: vec![],
+            out: vec![],
+        }
+    }
+
+    /// Returns the text of an inline link. Links to functions with pages of
+    /// their own are written as cross-references, e.g. `` `free`(3) ``.
+    fn link(&self, link: &entity::Link) -> String {
+        match &link.label {
+            Some(label) => label.clone(),
+            None if self.functions.contains(&link.target) => format!("`{}`(3)", link.target),
+            None if self.index.anchor(&link.target).is_some() => format!("`{}`", link.target),
+            None => link.target.clone(),
+        }
+    }
+
+    /// Returns the page name of the module being visited.
+    fn module(&self) -> &str {
+        &self.modules[self.current]
+    }
+
+    /// Returns the name of the file of the module being visited, as it would
+    /// be included, if set.
+    fn header(&self) -> Option<&str> {
+        self.file
+            .as_deref()
+            .and_then(|file| Path::new(file).file_name())
+            .and_then(|name| name.to_str())
+    }
+
+    /// Generates the `SYNOPSIS` section of a page, with the `#include` of the
+    /// module's file followed by the given declarations.
+    fn synopsis(&self, declarations: &[String]) -> String {
+        let mut roff = String::from(".SH SYNOPSIS\n.nf\n");
+
+        if let Some(header) = self.header() {
+            roff.push_str(format!(".B #include <{}>\n", roff_escape(header)).as_str());
+
+            if !declarations.is_empty() {
+                roff.push_str(".PP\n");
+            }
+        }
+
+        for declaration in declarations {
+            roff.push_str(format!(".B \"{};\"\n", roff_escape(declaration)).as_str());
+        }
+
+        roff.push_str(".fi\n");
+
+        roff
+    }
+
+    /// Generates the page of a function.
+    fn function_page(
+        &self,
+        function: &entity::Function,
+        comment: Option<&entity::DocComment>,
+    ) -> String {
+        let mut roff = roff_header(&function.name, self.module());
+        let desc = comment.map(summary).unwrap_or_default();
+
+        roff.push_str(&roff_name(&function.name, &desc));
+        roff.push_str(&self.synopsis(&[function_signature(function)]));
+        roff.push_str(".SH DESCRIPTION\n");
+
+        let Some(c) = comment else {
+            roff.push_str(".PP\nNo documentation available.\n");
+            roff.push_str(format!(".SH SEE ALSO\n{}\n", roff_xref(self.module())).as_str());
+            return roff;
+        };
+
+        if let Some(deprecated) = &c.deprecated {
+            match deprecated.as_str() {
+                "" => roff.push_str(".PP\n\\fBDeprecated.\\fR\n"),
+                _ => roff.push_str(
+                    format!(".PP\n\\fBDeprecated:\\fR {}\n", roff_inline(deprecated)).as_str(),
+                ),
+            }
+        }
+
+        roff.push_str(&roff_text(&c.comment));
+
+        let params = function
+            .params
+            .iter()
+            .filter_map(|param| param.name.as_deref())
+            .filter_map(|name| c.params.iter().find(|p| p.name == name));
+
+        for param in params {
+            let name = roff_escape(&param.name);
+
+            match param.direction {
+                Some(direction) => {
+                    roff.push_str(format!(".TP\n.IR \"{}\" \" ({})\"\n", name, direction).as_str())
+                }
+                None => roff.push_str(format!(".TP\n.I \"{}\"\n", name).as_str()),
+            }
+
+            roff.push_str(&roff_line(&roff_inline(&param.description)));
+        }
+
+        if c.retval.is_some() || !c.retvals.is_empty() {
+            roff.push_str(".SH RETURN VALUE\n");
+        }
+
+        if let Some(retval) = &c.retval {
+            roff.push_str(&roff_text(&retval.description));
+        }
+
+        for retval in &c.retvals {
+            roff.push_str(format!(".TP\n.B \"{}\"\n", roff_escape(&retval.value)).as_str());
+            roff.push_str(&roff_line(&roff_inline(&retval.description)));
+        }
+
+        if !c.notes.is_empty() || !c.warnings.is_empty() {
+            roff.push_str(".SH NOTES\n");
+        }
+
+        for note in &c.notes {
+            roff.push_str(".PP\n");
+            roff.push_str(&roff_line(&roff_inline(note)));
+        }
+
+        for warning in &c.warnings {
+            roff.push_str(format!(".PP\n\\fBWarning:\\fR {}\n", roff_inline(warning)).as_str());
+        }
+
+        // Pages are cross-referenced by name, so a `@see` naming a function
+        // with a page of its own, with or without parentheses, links to it.
+        let mut see = c
+            .see
+            .iter()
+            .map(|see| match see.trim().trim_end_matches("()") {
+                name if self.functions.contains(name) => roff_xref(name),
+                _ => roff_inline(see),
+            })
+            .collect::<Vec<_>>();
+
+        see.push(roff_xref(self.module()));
+
+        roff.push_str(format!(".SH SEE ALSO\n{}\n", see.join(",\n")).as_str());
+
+        roff
+    }
+
+    /// Lists a node on the overview page of the module being visited, under
+    /// the given group.
+    ///
+    /// # Returns
+    ///
+    /// The node's doc comment, if any, with inline links resolved.
+    fn entry(&mut self, node: &Node, group: &'static str) -> Option<entity::DocComment> {
+        let comment = node
+            .get_comment()
+            .map(|c| c.map_text(|text| replace_links(text, |link| self.link(link))));
+
+        self.entries.push(Entry {
+            group,
+            title: node
+                .get_value()
+                .as_ref()
+                .map(NodeTypes::title)
+                .unwrap_or_default(),
+            desc: comment.as_ref().map(summary).unwrap_or_default(),
+        });
+
+        comment
+    }
+
+    /// Generates the overview page of the module being visited.
+    fn module_page(&self) -> String {
+        let name = self.module();
+        let desc = match self.header() {
+            Some(header) => format!("declarations of `{}`", header),
+            None => String::from("declarations"),
+        };
+
+        let mut roff = roff_header(name, name);
+        roff.push_str(&roff_name(name, &desc));
+        roff.push_str(&self.synopsis(&[]));
+        roff.push_str(".SH DESCRIPTION\n");
+
+        for group in GROUPS {
+            let mut entries = self
+                .entries
+                .iter()
+                .filter(|entry| entry.group == *group)
+                .peekable();
+
+            if entries.peek().is_none() {
+                continue;
+            }
+
+            roff.push_str(format!(".SS {}\n", group).as_str());
+
+            for entry in entries {
+                match entry.group {
+                    "Functions" => {
+                        roff.push_str(format!(".TP\n{}\n", roff_xref(&entry.title)).as_str())
+                    }
+                    _ => roff
+                        .push_str(format!(".TP\n.B \"{}\"\n", roff_escape(&entry.title)).as_str()),
+                }
+
+                if !entry.desc.is_empty() {
+                    roff.push_str(&roff_line(&roff_inline(&entry.desc)));
+                }
+            }
+        }
+
+        let pages = self
+            .entries
+            .iter()
+            .filter(|entry| entry.group == "Functions")
+            .map(|entry| roff_xref(&entry.title))
+            .collect::<Vec<_>>();
+
+        if !pages.is_empty() {
+            roff.push_str(format!(".SH SEE ALSO\n{}\n", pages.join(",\n")).as_str());
+        }
+
+        roff
+    }
+}
+
+impl<'i> Generator for ManGenerator<'i> {
+    fn begin(&mut self, asts: &[AST]) {
+        for node in asts.iter().flat_map(AST::get_iter) {
+            if let Some(NodeTypes::Function(function)) = node.get_value() {
+                self.functions.insert(function.name.clone());
+            }
+        }
+
+        let mut taken = self.functions.clone();
+        self.modules = asts
+            .iter()
+            .map(|ast| module_name(ast, &mut taken))
+            .collect();
+    }
+
+    fn begin_module(&mut self, ast: &AST) {
+        self.file = ast.get_file().map(String::from);
+        self.entries.clear();
+    }
+
+    fn visit_function(&mut self, node: &Node, function: &entity::Function) {
+        let comment = self.entry(node, "Functions");
+        let path = format!("man3/{}.3", function.name);
+
+        if self.out.iter().all(|x| x.path != path) {
+            let contents = self.function_page(function, comment.as_ref());
+            self.out.push(Page { path, contents });
+        }
+    }
+
+    fn visit_struct(&mut self, node: &Node, _struct: &entity::Struct) {
+        self.entry(node, "Types");
+    }
+
+    fn visit_union(&mut self, node: &Node, _union: &entity::Union) {
+        self.entry(node, "Types");
+    }
+
+    fn visit_enum(&mut self, node: &Node, _enum: &entity::Enum) {
+        self.entry(node, "Types");
+    }
+
+    fn visit_typedef(&mut self, node: &Node, _typedef: &entity::Typedef) {
+        self.entry(node, "Types");
+    }
+
+    fn visit_macro(&mut self, node: &Node, _macro: &entity::Macro) {
+        self.entry(node, "Macros");
+    }
+
+    fn visit_variable(&mut self, node: &Node, _variable: &entity::Variable) {
+        self.entry(node, "Variables");
+    }
+
+    fn end_module(&mut self, _ast: &AST) {
+        self.out.push(Page {
+            path: format!("man3/{}.3", self.module()),
+            contents: self.module_page(),
+        });
+        self.current += 1;
+    }
+
+    fn finish(&mut self) -> Output {
+        Output::Site(std::mem::take(&mut self.out))
+    }
+}
@@ -3,11 +3,21 @@
 //! This module is responsible for generating markdown documentation
 //! from the Abstract Syntax Tree (AST) created by parsing the source code.
 
-use crate::ast::{Node, NodeTypes, AST};
+use std::path::Path;
+
+use crate::ast::{Node, SymbolIndex, AST};
 use crate::entity;
+use crate::generator::{
+    fence_marker, function_signature, html_escape, summarize, type_runs, Generator, Output,
+};
+use crate::parser::replace_links;
+use crate::template::{Templates, Value};
 
 /// Generates markdown documentation from an AST.
 ///
+/// Inline links in doc comments are resolved against the symbols in this
+/// AST only; use [`generate_md_with_index`] to link across files.
+///
 /// # Arguments
 ///
 /// * `ast` - A reference to the AST to generate documentation from.
@@ -16,10 +26,32 @@ use crate::entity;
 ///
 /// A `String` containing the generated markdown documentation.
 pub fn generate_md(ast: &AST) -> String {
-    ast.get_iter()
-        .map(Node::md_gen_visit)
-        .collect::<Vec<_>>()
-        .join("\n\n")
+    generate_md_with_index(ast, &SymbolIndex::from_ast(ast))
+}
+
+/// Generates markdown documentation from an AST, resolving inline links in
+/// doc comments against the given symbol index.
+///
+/// Unlike [`MarkdownGenerator`], this only generates the sections of the
+/// nodes, without a heading for the module.
+///
+/// # Arguments
+///
+/// * `ast` - A reference to the AST to generate documentation from.
+/// * `index` - The symbols that links may refer to.
+///
+/// # Returns
+///
+/// A `String` containing the generated markdown documentation.
+pub fn generate_md_with_index(ast: &AST, index: &SymbolIndex) -> String {
+    let mut generator = MarkdownGenerator::new(index);
+    generator.begin_module(ast);
+
+    for node in ast.get_iter() {
+        generator.visit_node(node);
+    }
+
+    generator.sections.join("\n\n")
 }
 
 /// Replaces leading whitespace with non-breaking spaces for markdown formatting.
@@ -40,134 +72,473 @@ fn replace_leading_whitespace(s: &str) -> String {
 }
 
 /// Escapes special characters in the content for proper markdown rendering.
+///
+/// Fenced code blocks are left untouched, so their indentation is kept.
 fn md_escape(content: &str) -> String {
+    let mut fence: Option<&str> = None;
+
     content
         .split("\n")
-        .map(replace_leading_whitespace)
+        .map(|line| match (fence, fence_marker(line)) {
+            (Some(open), _) => {
+                if line.trim() == open {
+                    fence = None;
+                }
+                String::from(line)
+            }
+            (None, Some(marker)) => {
+                fence = Some(marker);
+                String::from(line.trim_start())
+            }
+            (None, None) => replace_leading_whitespace(line),
+        })
         .collect::<Vec<String>>()
         .join("\n")
 }
 
-impl<'a> Node<'a> {
-    /// Generates markdown for this node.
-    fn md_gen_visit(&self) -> String {
-        match self.get_value() {
-            Some(node_type) => node_type.md_gen_visit(self.get_comment()),
-            None => "".to_string(),
-        }
+/// Escapes pipe characters so content can be placed in a markdown table cell.
+pub(crate) fn table_escape(content: &str) -> String {
+    content.replace('|', "\\|")
+}
+
+/// Formats an inline link as a markdown link to the section of its target,
+/// or as plain text if the target is not documented.
+fn md_link(link: &entity::Link, index: &SymbolIndex) -> String {
+    let text = link.label.as_deref().unwrap_or(&link.target);
+
+    match index.anchor(&link.target) {
+        Some(anchor) => format!("[{}](#{})", text, anchor),
+        None => String::from(text),
     }
 }
 
-impl<'a> NodeTypes<'a> {
-    /// Generates markdown for this node type.
-    fn md_gen_visit(&self, comment: Option<&'a entity::DocComment>) -> String {
-        match self {
-            NodeTypes::Enum(_) => self.md_gen_visit_enum(comment),
-            NodeTypes::Function(_) => self.md_gen_visit_function(comment),
-            NodeTypes::Struct(_) => self.md_gen_visit_struct(comment),
-        }
+/// Formats a C snippet as inline code, linking the names of documented types
+/// to their sections.
+fn md_code_span(code: &str, index: &SymbolIndex) -> String {
+    type_runs(code, index, "")
+        .into_iter()
+        .map(|run| match run {
+            (text, Some(anchor)) => format!("[`{}`](#{})", text, anchor),
+            (text, None) => format!("`{}`", text),
+        })
+        .collect()
+}
+
+/// Formats a C declaration as a code block, linking the names of documented
+/// types other than `own` to their sections.
+///
+/// Markdown does not allow links in fenced code blocks, so a declaration
+/// that mentions a documented type is written as an HTML `<pre>` block.
+fn md_code_block(code: &str, index: &SymbolIndex, own: &str) -> String {
+    let runs = type_runs(code, index, own);
+
+    if runs.iter().all(|(_, anchor)| anchor.is_none()) {
+        return format!("```c\n{}\n```", code);
     }
 
-    /// Generates markdown for an enum.
-    fn md_gen_visit_enum(&self, comment: Option<&'a entity::DocComment>) -> String {
-        let comment_str: &str;
+    let html = runs
+        .into_iter()
+        .map(|run| match run {
+            (text, Some(anchor)) => format!("<a href=\"#{}\">{}</a>", anchor, html_escape(text)),
+            (text, None) => html_escape(text),
+        })
+        .collect::<String>();
 
-        match comment {
-            Some(c) => comment_str = c.comment.as_str(),
-            None => comment_str = "No documentation available",
-        }
+    format!("<pre><code>{}</code></pre>", html)
+}
 
-        let NodeTypes::Enum(node) = self else { panic!("Wrong type") };
-        let mut md = String::new();
-        md.push_str(format!("## Enum `{}`\n\n", node.name).as_str());
-        md.push_str(format!("{}\n\n", md_escape(comment_str)).as_str());
-        md.push_str("**Variants**:\n");
+/// Builds the template value of a block tag, or nothing if the tag was not
+/// given.
+fn tag_value(label: &str, items: &[String]) -> Option<Value> {
+    let single = match items {
+        [] => return None,
+        [item] => Some(item.as_str()),
+        _ => None,
+    };
 
-        for variant in &node.variants {
-            md.push_str(format!("- `{}`\n", variant).as_str());
-        }
+    let items = items
+        .iter()
+        .map(|item| Value::Map(vec![("text", Value::text(item))]))
+        .collect();
 
-        md
+    Some(Value::Map(vec![
+        ("label", Value::text(label)),
+        ("text", Value::text(single.unwrap_or_default())),
+        ("single", Value::Bool(single.is_some())),
+        ("items", Value::List(items)),
+    ]))
+}
+
+/// Builds the template value of a doc comment.
+///
+/// Every block tag that was given is both a field of its own and an item of
+/// `tags`, which lists the tags that follow the description in order.
+fn doc_value(comment: &entity::DocComment) -> Value {
+    let c = comment;
+    let deprecated: Vec<String> = c.deprecated.iter().cloned().collect();
+    let brief: Vec<String> = c.brief.iter().cloned().collect();
+    let since: Vec<String> = c.since.iter().cloned().collect();
+
+    let tags: Vec<(&'static str, Value)> = [
+        ("since", "Since", &since),
+        ("authors", "Author", &c.authors),
+        ("pre", "Precondition", &c.pre),
+        ("post", "Postcondition", &c.post),
+        ("throws", "Throws", &c.throws),
+        ("notes", "Note", &c.notes),
+        ("warnings", "Warning", &c.warnings),
+        ("see", "See also", &c.see),
+        ("todos", "Todo", &c.todos),
+    ]
+    .into_iter()
+    .filter_map(|(key, label, items)| tag_value(label, items).map(|tag| (key, tag)))
+    .collect();
+
+    let mut fields = vec![("description", Value::Text(md_escape(&c.comment)))];
+    fields.extend(tag_value("Deprecated", &deprecated).map(|tag| ("deprecated", tag)));
+    fields.extend(tag_value("Brief", &brief).map(|tag| ("brief", tag)));
+    fields.push((
+        "tags",
+        Value::List(tags.iter().map(|(_, tag)| tag.clone()).collect()),
+    ));
+    fields.extend(tags);
+
+    if let Some(r) = &c.retval {
+        fields.push((
+            "return",
+            Value::Map(vec![("text", Value::text(&r.description))]),
+        ));
     }
 
-    /// Generates markdown for a function.
-    fn md_gen_visit_function(&self, comment: Option<&'a entity::DocComment>) -> String {
-        let mut ret_str: &str = "No description";
-        let mut comment_str: &str = "No documentation available";
+    let params = c.params.iter().map(|p| {
+        Value::Map(vec![
+            ("name", Value::text(&p.name)),
+            ("description", Value::text(&p.description)),
+            (
+                "direction",
+                Value::Text(p.direction.map(|d| d.to_string()).unwrap_or_default()),
+            ),
+        ])
+    });
 
-        if let Some(c) = comment {
-            comment_str = c.comment.as_str();
+    let retvals = c.retvals.iter().map(|r| {
+        Value::Map(vec![
+            ("value", Value::text(&r.value)),
+            ("description", Value::text(&r.description)),
+        ])
+    });
 
-            if let Some(r) = &c.retval {
-                ret_str = r.description.as_str();
-            };
-        }
+    fields.push(("params", Value::List(params.collect())));
+    fields.push(("retvals", Value::List(retvals.collect())));
 
-        let NodeTypes::Function(node) = self else { panic!("Wrong type") };
-        let mut md = String::new();
-        md.push_str(format!("## Function `{}`\n\n", node.name).as_str());
-        md.push_str(
-            format!(
-                "```c\n{} {}({})\n```\n\n",
-                node.return_type,
-                node.name,
-                node.params.join(", ")
-            )
-            .as_str(),
-        );
-        md.push_str(format!("{}\n\n", md_escape(comment_str)).as_str());
-
-        if node.return_type != "void" {
-            md.push_str(
-                format!("**Returns**:\n\n`{}`: {}\n\n", node.return_type, ret_str).as_str(),
-            );
-        }
+    Value::Map(fields)
+}
 
-        md.push_str("**Parameters**:\n");
-
-        if let Some(c) = comment {
-            node.params
-                .iter()
-                .map(|x| x.split(' ').last().unwrap())
-                .fold((), |_, name| {
-                    let desc = match c.params.iter().find(|p| p.name == name) {
-                        Some(p) => p.description.as_str(),
-                        None => "No description",
-                    };
-
-                    md.push_str(format!("- `{}`: {}\n", name, desc).as_str())
-                })
-        } else {
-            node.params
-                .iter()
-                .map(|param| param.split(' ').last().unwrap())
-                .fold((), |_, name| {
-                    md.push_str(format!("- `{}`\n", name).as_str())
-                })
-        }
+/// Builds the template value of a named parameter, merged with its
+/// documentation.
+fn param_value(name: &str, ty: &str, comment: Option<&entity::DocComment>) -> Value {
+    let doc = comment.and_then(|c| c.params.iter().find(|p| p.name == name));
+    let direction = doc.and_then(|p| p.direction).map(|d| d.to_string());
+
+    Value::Map(vec![
+        ("name", Value::text(name)),
+        ("type", Value::text(ty)),
+        ("direction", Value::Text(direction.unwrap_or_default())),
+        (
+            "description",
+            Value::text(doc.map_or("", |p| p.description.as_str())),
+        ),
+        ("documented", Value::Bool(comment.is_some())),
+        ("described", Value::Bool(doc.is_some())),
+    ])
+}
+
+/// Builds the template values of struct members, nesting the members of
+/// inline struct and union definitions.
+fn members_value(members: &[entity::Member], depth: usize, index: &SymbolIndex) -> Value {
+    let members = members.iter().map(|member| {
+        let declaration = member.to_string();
+        let nested = match &member.aggregate {
+            Some(aggregate) => members_value(aggregate.members(), depth + 1, index),
+            None => Value::List(vec![]),
+        };
+
+        Value::Map(vec![
+            (
+                "name",
+                Value::text(member.name.as_deref().unwrap_or_default()),
+            ),
+            ("code", Value::Text(md_code_span(&declaration, index))),
+            ("declaration", Value::Text(declaration)),
+            (
+                "description",
+                Value::text(member.description.as_deref().unwrap_or_default()),
+            ),
+            ("indent", Value::Text("  ".repeat(depth))),
+            ("members", nested),
+        ])
+    });
+
+    Value::List(members.collect())
+}
+
+/// Builds the template fields of a function.
+fn function_fields(
+    node: &entity::Function,
+    comment: Option<&entity::DocComment>,
+    index: &SymbolIndex,
+) -> Vec<(&'static str, Value)> {
+    let signature = function_signature(node);
+    let void = node.return_type.is_void();
+    let retvals = comment.map_or(false, |c| !c.retvals.is_empty());
+
+    let params = node.params.iter().filter_map(|param| {
+        let name = param.name.as_deref()?;
+        Some(param_value(name, &param.ty.to_string(), comment))
+    });
+
+    vec![
+        ("code", Value::Text(md_code_block(&signature, index, ""))),
+        ("signature", Value::Text(signature)),
+        ("return_type", Value::Text(node.return_type.to_string())),
+        ("void", Value::Bool(void)),
+        ("returns", Value::Bool(!void || retvals)),
+        ("params", Value::List(params.collect())),
+    ]
+}
 
-        md
+/// Builds the template fields of a macro.
+fn macro_fields(
+    node: &entity::Macro,
+    comment: Option<&entity::DocComment>,
+) -> Vec<(&'static str, Value)> {
+    let signature = entity::Macro {
+        value: summarize(&node.value),
+        ..node.clone()
     }
+    .to_string();
 
-    /// Generates markdown for a struct.
-    fn md_gen_visit_struct(&self, comment: Option<&'a entity::DocComment>) -> String {
-        let comment_str: &str;
+    let returns = comment.map_or(false, |c| c.retval.is_some() || !c.retvals.is_empty());
+    let params = node
+        .params
+        .iter()
+        .flatten()
+        .map(|p| param_value(p, "", comment));
 
-        match comment {
-            Some(c) => comment_str = c.comment.as_str(),
-            None => comment_str = "No documentation available",
-        }
+    vec![
+        ("code", Value::Text(format!("```c\n{}\n```", signature))),
+        ("signature", Value::Text(signature)),
+        ("value", Value::text(&node.value)),
+        ("returns", Value::Bool(returns)),
+        ("params", Value::List(params.collect())),
+    ]
+}
 
-        let NodeTypes::Struct(node) = self else { panic!("Wrong type") };
-        let mut md = String::new();
-        md.push_str(format!("## Struct `{}`\n\n", node.name).as_str());
-        md.push_str(format!("{}\n\n", md_escape(comment_str)).as_str());
-        md.push_str("**Members**:\n");
+/// Builds the template fields of an enum.
+fn enum_fields(node: &entity::Enum) -> Vec<(&'static str, Value)> {
+    let variants = node.variants.iter().map(|variant| {
+        Value::Map(vec![
+            ("name", Value::text(&variant.name)),
+            (
+                "value",
+                Value::text(variant.value.as_deref().unwrap_or_default()),
+            ),
+            (
+                "description",
+                Value::text(variant.description.as_deref().unwrap_or_default()),
+            ),
+        ])
+    });
 
-        for member in &node.members {
-            md.push_str(format!("- `{}`\n", member).as_str());
+    vec![("variants", Value::List(variants.collect()))]
+}
+
+/// Builds the template fields of a typedef.
+fn typedef_fields(node: &entity::Typedef, index: &SymbolIndex) -> Vec<(&'static str, Value)> {
+    let signature = node.to_string();
+
+    vec![
+        (
+            "code",
+            Value::Text(md_code_block(&signature, index, &node.name)),
+        ),
+        ("signature", Value::Text(signature)),
+        ("type", Value::Text(node.ty.to_string())),
+    ]
+}
+
+/// Builds the template fields of a global variable.
+fn variable_fields(node: &entity::Variable, index: &SymbolIndex) -> Vec<(&'static str, Value)> {
+    let signature = entity::Variable {
+        initializer: node.initializer.as_deref().map(summarize),
+        ..node.clone()
+    }
+    .to_string();
+
+    vec![
+        ("code", Value::Text(md_code_block(&signature, index, ""))),
+        ("signature", Value::Text(signature)),
+        ("type", Value::Text(node.ty.to_string())),
+        (
+            "initializer",
+            Value::text(node.initializer.as_deref().unwrap_or_default()),
+        ),
+    ]
+}
+
+/// Generates markdown documentation, with a `# Module` heading and a section
+/// per node for every module.
+///
+/// Every section is rendered through the template named after the kind of
+/// its node, such as `function`, and every module through the `module`
+/// template. See the [`template`](crate::template) module for the template
+/// syntax.
+pub struct MarkdownGenerator<'i> {
+    /// The symbols that inline links and type names may refer to.
+    index: &'i SymbolIndex,
+
+    /// The templates sections and modules are rendered through.
+    templates: Templates,
+
+    /// The path of the file of the module being visited, if set.
+    file: Option<String>,
+
+    /// The sections of the module being visited.
+    sections: Vec<String>,
+
+    /// The documentation of the modules visited so far.
+    docs: String,
+}
+
+impl<'i> MarkdownGenerator<'i> {
+    /// Creates a generator that links to the symbols in the given index,
+    /// using the built-in templates.
+    pub fn new(index: &'i SymbolIndex) -> Self {
+        Self::with_templates(index, Templates::builtin())
+    }
+
+    /// Creates a generator that links to the symbols in the given index,
+    /// rendering through the given templates.
+    pub fn with_templates(index: &'i SymbolIndex, templates: Templates) -> Self {
+        Self {
+            index,
+            templates,
+            file: None,
+            sections: vec![],
+            docs: String::new(),
         }
+    }
+
+    /// Adds the section documenting a node to the module being visited.
+    ///
+    /// Inline links in the node's doc comment are resolved before `fields` is
+    /// called with it, and the fields it returns are added to those every
+    /// node has.
+    fn section(
+        &mut self,
+        node: &Node,
+        fields: impl FnOnce(Option<&entity::DocComment>, &SymbolIndex) -> Vec<(&'static str, Value)>,
+    ) {
+        let Some(value) = node.get_value() else {
+            return;
+        };
+
+        let comment = node
+            .get_comment()
+            .map(|c| c.map_text(|text| replace_links(text, |link| md_link(link, self.index))));
+
+        let line = match node.get_span().line {
+            0 => String::new(),
+            line => line.to_string(),
+        };
+
+        let mut context = vec![
+            ("kind", Value::text(value.kind())),
+            ("name", Value::text(value.name())),
+            ("alias", Value::text(value.alias().unwrap_or_default())),
+            ("title", Value::Text(value.title())),
+            ("anchor", Value::Text(value.anchor())),
+            (
+                "file",
+                Value::text(self.file.as_deref().unwrap_or_default()),
+            ),
+            ("line", Value::Text(line)),
+        ];
+
+        context.extend(comment.as_ref().map(|c| ("doc", doc_value(c))));
+        context.extend(fields(comment.as_ref(), self.index));
+
+        let name = value.kind().to_lowercase();
+        let md = self.templates.render(&name, &Value::Map(context));
+        self.sections.push(md);
+    }
+}
+
+impl<'i> Generator for MarkdownGenerator<'i> {
+    fn begin_module(&mut self, ast: &AST) {
+        self.file = ast.get_file().map(String::from);
+        self.sections.clear();
+    }
+
+    fn visit_function(&mut self, node: &Node, function: &entity::Function) {
+        self.section(node, |comment, index| {
+            function_fields(function, comment, index)
+        });
+    }
+
+    fn visit_struct(&mut self, node: &Node, x: &entity::Struct) {
+        self.section(node, |_, index| {
+            vec![("members", members_value(&x.members, 0, index))]
+        });
+    }
+
+    fn visit_union(&mut self, node: &Node, x: &entity::Union) {
+        self.section(node, |_, index| {
+            vec![("members", members_value(&x.members, 0, index))]
+        });
+    }
+
+    fn visit_enum(&mut self, node: &Node, x: &entity::Enum) {
+        self.section(node, |_, _| enum_fields(x));
+    }
+
+    fn visit_typedef(&mut self, node: &Node, typedef: &entity::Typedef) {
+        self.section(node, |_, index| typedef_fields(typedef, index));
+    }
+
+    fn visit_macro(&mut self, node: &Node, x: &entity::Macro) {
+        self.section(node, |comment, _| macro_fields(x, comment));
+    }
+
+    fn visit_variable(&mut self, node: &Node, variable: &entity::Variable) {
+        self.section(node, |_, index| variable_fields(variable, index));
+    }
+
+    fn end_module(&mut self, _ast: &AST) {
+        let name = self
+            .file
+            .as_deref()
+            .and_then(|file| Path::new(file).file_stem())
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+
+        let context = Value::Map(vec![
+            ("name", Value::text(name)),
+            (
+                "file",
+                Value::text(self.file.as_deref().unwrap_or_default()),
+            ),
+            (
+                "sections",
+                Value::List(self.sections.drain(..).map(Value::Text).collect()),
+            ),
+        ]);
+
+        self.docs
+            .push_str(&self.templates.render("module", &context));
+    }
 
-        md
+    fn finish(&mut self) -> Output {
+        Output::Document(std::mem::take(&mut self.docs))
     }
 }
@@ -4,21 +4,67 @@
 //! representations of code elements.
 
 use crate::entity;
+use crate::error::{ParseError, ParseErrorKind};
+use crate::span::Span;
 use crate::token;
 use crate::token::TokenValuePair;
+use once_cell::sync::Lazy;
 use regex::*;
 
 /// Represents the different types of parsed tokens.
+// Doc comments dwarf the other variants, but a file only holds a handful of
+// tokens, so boxing them is not worth the noise at every use.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub enum ParsedToken {
     DocComment(entity::DocComment),
     Struct(entity::Struct),
+    Union(entity::Union),
     Function(entity::Function),
     Enum(entity::Enum),
+    Typedef(entity::Typedef),
+    Macro(entity::Macro),
+    Variable(entity::Variable),
+
+    /// A declaration that matches none of the other tokens. It is kept so
+    /// that a doc comment in front of it is not attached to the next one.
+    Unknown(Span),
+}
+
+impl ParsedToken {
+    /// Returns the location of the parsed token in the source.
+    pub fn span(&self) -> Span {
+        match self {
+            ParsedToken::DocComment(x) => x.span,
+            ParsedToken::Struct(x) => x.span,
+            ParsedToken::Union(x) => x.span,
+            ParsedToken::Function(x) => x.span,
+            ParsedToken::Enum(x) => x.span,
+            ParsedToken::Typedef(x) => x.span,
+            ParsedToken::Macro(x) => x.span,
+            ParsedToken::Variable(x) => x.span,
+            ParsedToken::Unknown(x) => *x,
+        }
+    }
+
+    /// Sets the location of the parsed token in the source.
+    pub fn set_span(&mut self, span: Span) {
+        match self {
+            ParsedToken::DocComment(x) => x.span = span,
+            ParsedToken::Struct(x) => x.span = span,
+            ParsedToken::Union(x) => x.span = span,
+            ParsedToken::Function(x) => x.span = span,
+            ParsedToken::Enum(x) => x.span = span,
+            ParsedToken::Typedef(x) => x.span = span,
+            ParsedToken::Macro(x) => x.span = span,
+            ParsedToken::Variable(x) => x.span = span,
+            ParsedToken::Unknown(x) => *x = span,
+        }
+    }
 }
 
 /// Attempts to match a regular expression pattern against a source string.
-fn get_capture<'a, 'b>(pat: &'a str, src: &'b str) -> Option<Captures<'b>> {
+fn get_capture<'a>(pat: &str, src: &'a str) -> Option<Captures<'a>> {
     let re = RegexBuilder::new(pat)
         .dot_matches_new_line(true)
         .build()
@@ -27,171 +73,1123 @@ fn get_capture<'a, 'b>(pat: &'a str, src: &'b str) -> Option<Captures<'b>> {
     re.captures(src)
 }
 
-/// Splits a source string by a separator character, trimming whitespace.
-fn src_split(source: &str, sep: char) -> Vec<String> {
-    source
-        .split(sep)
+/// Returns an error for a declaration that could not be broken into its parts.
+fn malformed(message: &str) -> ParseError {
+    ParseError::new(ParseErrorKind::MalformedDeclaration, message)
+}
+
+/// Storage-class specifiers that may prefix a declaration.
+const STORAGE_CLASSES: &[&str] = &[
+    "static",
+    "extern",
+    "register",
+    "auto",
+    "typedef",
+    "_Thread_local",
+    "thread_local",
+];
+
+/// Function specifiers that may prefix a function declaration.
+const FUNCTION_SPECIFIERS: &[&str] = &["inline", "__inline", "__inline__", "_Noreturn", "noreturn"];
+
+/// Type qualifiers that may appear anywhere in a type.
+const QUALIFIERS: &[&str] = &[
+    "const",
+    "volatile",
+    "restrict",
+    "__restrict",
+    "__restrict__",
+    "_Atomic",
+];
+
+/// Keywords that can only be part of a type, never a declarator name.
+const TYPE_KEYWORDS: &[&str] = &[
+    "void", "char", "short", "int", "long", "float", "double", "signed", "unsigned", "_Bool",
+    "bool", "_Complex", "struct", "union", "enum",
+];
+
+/// Splits a source string by a separator character that is not nested in
+/// parentheses, brackets or braces, trimming whitespace.
+fn split_top_level(source: &str, sep: char) -> Vec<String> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in source.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ if c == sep && depth == 0 => {
+                parts.push(&source[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+
+    parts.push(&source[start..]);
+
+    parts
+        .into_iter()
         .map(str::trim)
-        .filter_map(|x| {
-            if x.is_empty() {
-                None
-            } else {
-                Some(String::from(x))
+        .filter(|x| !x.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Returns the byte offset just past the group that starts at the beginning
+/// of `source`, balancing `open` and `close` characters.
+fn group_end(source: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+
+    for (i, c) in source.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+
+            if depth == 0 {
+                return Some(i + c.len_utf8());
             }
-        })
-        .collect::<Vec<String>>()
+        }
+    }
+
+    None
+}
+
+/// Removes attributes from a declaration, returning the remaining text and
+/// the attributes in the order they appeared.
+fn take_attributes(source: &str) -> (String, Vec<String>) {
+    static RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"__attribute__\s*\(|__declspec\s*\(|\[\[").unwrap());
+
+    let mut text = String::new();
+    let mut attributes = vec![];
+    let mut rest = source;
+
+    while let Some(m) = RE.find(rest) {
+        text.push_str(&rest[..m.start()]);
+        text.push(' ');
+
+        let (start, open, close) = if m.as_str() == "[[" {
+            (m.start(), '[', ']')
+        } else {
+            (m.end() - 1, '(', ')')
+        };
+        let end = group_end(&rest[start..], open, close).map_or(rest.len(), |end| start + end);
+
+        attributes.push(rest[m.start()..end].to_string());
+        rest = &rest[end..];
+    }
+
+    text.push_str(rest);
+
+    (text, attributes)
+}
+
+/// Splits the specifiers of a declaration into its storage class, function
+/// specifiers and type.
+///
+/// Qualifiers before the first `*` qualify the base type, and those after a
+/// `*` qualify that pointer level, so `char *const` is a const pointer to
+/// `char`.
+fn parse_specifiers(source: &str) -> (Option<String>, Vec<String>, entity::Type) {
+    let mut storage = None;
+    let mut specifiers = vec![];
+    let mut ty = entity::Type::default();
+    let mut name = vec![];
+    let source = source.replace('*', " * ");
+
+    for word in source.split_whitespace() {
+        if word == "*" {
+            ty.pointers.push(vec![]);
+        } else if STORAGE_CLASSES.contains(&word) {
+            storage = Some(String::from(word));
+        } else if FUNCTION_SPECIFIERS.contains(&word) {
+            specifiers.push(String::from(word));
+        } else if QUALIFIERS.contains(&word) {
+            match ty.pointers.last_mut() {
+                Some(level) => level.push(String::from(word)),
+                None => ty.qualifiers.push(String::from(word)),
+            }
+        } else {
+            name.push(word);
+        }
+    }
+
+    ty.name = name.join(" ");
+
+    (storage, specifiers, ty)
+}
+
+/// Splits trailing array extents off a declarator, e.g. `arr[2][3]` into
+/// `arr` and `["2", "3"]`.
+fn split_arrays(source: &str) -> (&str, Vec<String>) {
+    let Some(open) = source.find('[') else {
+        return (source, vec![]);
+    };
+
+    let arrays = source[open..]
+        .split(['[', ']'])
+        .map(str::trim)
+        .skip(1)
+        .step_by(2)
+        .map(String::from)
+        .collect();
+
+    (&source[..open], arrays)
+}
+
+/// Returns the name in an abstract declarator such as `*const cb`, if any.
+fn declarator_name(declarator: &str) -> Option<String> {
+    declarator
+        .replace('*', " ")
+        .split_whitespace()
+        .rfind(|word| !QUALIFIERS.contains(word))
+        .map(String::from)
+}
+
+/// Extracts the text of a doc comment as a single line.
+fn doc_text(comment: &str) -> String {
+    comment
+        .trim_start_matches("/**")
+        .trim_start_matches('<')
+        .trim_end_matches("*/")
+        .lines()
+        .map(|line| line.trim().trim_start_matches('*').trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits the body of a struct or enum into its declarations, pairing each
+/// with the text of its doc comment.
+///
+/// A leading `/** ... */` comment documents the declaration that follows it,
+/// and a trailing `/**< ... */` comment documents the one that precedes it.
+fn split_documented(body: &str, sep: char) -> Vec<(String, Option<String>)> {
+    let mut items: Vec<(String, Option<String>)> = vec![];
+    let mut current = String::new();
+    let mut pending = None;
+    let mut depth = 0;
+    let mut rest = body;
+
+    while let Some(c) = rest.chars().next() {
+        if depth == 0 && rest.starts_with("/**") {
+            let end = rest.find("*/").map_or(rest.len(), |end| end + 2);
+            let comment = &rest[..end];
+            rest = &rest[end..];
+
+            if !comment.starts_with("/**<") || !current.trim().is_empty() {
+                pending = Some(doc_text(comment));
+            } else if let Some(last) = items.last_mut() {
+                last.1 = Some(doc_text(comment));
+            }
+
+            continue;
+        }
+
+        rest = &rest[c.len_utf8()..];
+
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ if c == sep && depth == 0 => {
+                items.push((current.trim().to_string(), pending.take()));
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+
+        current.push(c);
+    }
+
+    items.push((current.trim().to_string(), pending.take()));
+    items.retain(|(text, _)| !text.is_empty());
+
+    items
+}
+
+/// Parses a member declaration that may declare several members, such as
+/// `int x, *y;`, giving each the same description.
+fn parse_members(source: &str, description: Option<String>) -> Vec<entity::Member> {
+    let mut declarators = split_top_level(source, ',').into_iter();
+
+    let Some(first) = declarators
+        .next()
+        .and_then(|x| entity::Member::parse(&x).ok())
+    else {
+        return vec![];
+    };
+
+    let base = entity::Type {
+        pointers: vec![],
+        ..first.ty.clone()
+    };
+
+    let mut members = vec![first];
+    members
+        .extend(declarators.filter_map(|x| entity::Member::parse(&format!("{} {}", base, x)).ok()));
+
+    for member in &mut members {
+        member.description = description.clone();
+    }
+
+    members
+}
+
+/// Parses a variable declaration that may declare several variables, such as
+/// `static int a = 1, *b;`, giving each the storage class and base type of
+/// the first.
+fn parse_variables(source: &str) -> Result<Vec<entity::Variable>, ParseError> {
+    let mut declarators = split_top_level(source.trim().trim_end_matches(';'), ',').into_iter();
+    let first = entity::Variable::parse(&declarators.next().unwrap_or_default())?;
+
+    let base = entity::Type {
+        pointers: vec![],
+        ..first.ty.clone()
+    };
+    let prefix = match &first.storage {
+        Some(storage) => format!("{} {}", storage, base),
+        None => base.to_string(),
+    };
+
+    let mut variables = vec![first];
+    for declarator in declarators {
+        variables.push(entity::Variable::parse(&format!(
+            "{} {}",
+            prefix, declarator
+        ))?);
+    }
+
+    Ok(variables)
+}
+
+/// Removes the declarator name from the end of a type name, if there is one.
+///
+/// A single word such as `size_t` is taken to be an unnamed type, as is a
+/// trailing type keyword or struct, union or enum tag.
+fn take_declarator_name(ty: &mut entity::Type) -> Option<String> {
+    let (rest, name) = ty.name.rsplit_once(' ')?;
+    let tag = rest.rsplit(' ').next()?;
+
+    if TYPE_KEYWORDS.contains(&name) || ["struct", "union", "enum"].contains(&tag) {
+        return None;
+    }
+
+    let name = String::from(name);
+    ty.name = String::from(rest);
+
+    Some(name)
+}
+
+/// Parses a comma-separated parameter list, treating `(void)` as empty.
+fn parse_params(source: &str) -> Result<Vec<entity::Parameter>, ParseError> {
+    let params = split_top_level(source, ',')
+        .iter()
+        .map(|param| entity::Parameter::parse(param))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match params.as_slice() {
+        [param] if param.name.is_none() && param.ty.is_void() => Ok(vec![]),
+        _ => Ok(params),
+    }
 }
 
 /// A trait for types that can be parsed from a string.
 pub trait Parse: 'static {
     /// Attempts to parse an instance of Self from a string.
-    fn parse(src: &str) -> Option<Self>
+    ///
+    /// The span of a returned error is empty, since only the text of the
+    /// token is known here.
+    fn parse(src: &str) -> Result<Self, ParseError>
     where
         Self: Sized;
 }
 
-impl Parse for entity::DocComment {
-    fn parse(src: &str) -> Option<Self> {
-        static PARAM_PATTERN: &str =
-            r"[^\S\r\n]*@param[^\S\r\n]+(?<name>\w+)[^\S\r\n]+(?<desc>([^\r\n]+))";
+/// Parses the direction of a parameter, such as `in,out` in `@param[in,out]`.
+fn direction(source: &str) -> Option<entity::Direction> {
+    let words = source
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
 
-        static RETVAL_PATTERN: &str = r"[^\S\r\n]*@return[^\S\r\n]+(?<desc>([^\r\n])+)";
+    match words.as_slice() {
+        ["in"] => Some(entity::Direction::In),
+        ["out"] => Some(entity::Direction::Out),
+        ["in", "out"] | ["out", "in"] | ["inout"] => Some(entity::Direction::InOut),
+        _ => None,
+    }
+}
+
+/// Represents the block tags recognized in a doc comment.
+#[derive(Debug, Clone, Copy)]
+enum Tag {
+    Brief,
+    Param,
+    Return,
+    RetVal,
+    Note,
+    Warning,
+    See,
+    Since,
+    Deprecated,
+    Throws,
+    Author,
+    Todo,
+    Pre,
+    Post,
+}
+
+impl Tag {
+    /// The pattern of the text of a `@param` tag, such as `[out] len The length`.
+    const PARAM_PATTERN: &'static str = r"^(?:\[(?<dir>[^\]]*)\]\s*)?(?<name>\w+)\s*(?<desc>.*)$";
+
+    /// Returns the tag with the given name or alias, if it is recognized.
+    fn from_name(name: &str) -> Option<Self> {
+        let tag = match name {
+            "brief" | "short" => Tag::Brief,
+            "param" | "arg" => Tag::Param,
+            "return" | "returns" | "result" => Tag::Return,
+            "retval" => Tag::RetVal,
+            "note" => Tag::Note,
+            "warning" => Tag::Warning,
+            "see" => Tag::See,
+            "since" => Tag::Since,
+            "deprecated" => Tag::Deprecated,
+            "throws" | "throw" | "exception" => Tag::Throws,
+            "author" => Tag::Author,
+            "todo" => Tag::Todo,
+            "pre" => Tag::Pre,
+            "post" => Tag::Post,
+            _ => return None,
+        };
+
+        Some(tag)
+    }
 
-        enum Section {
-            Description,
-            Param,
-            RetVal,
+    /// Adds a new entry for this tag to a doc comment.
+    ///
+    /// `param_re` is [`Tag::PARAM_PATTERN`], compiled once by the caller.
+    fn start(self, doc: &mut entity::DocComment, text: &str, param_re: &Regex) {
+        let text = String::from(text);
+
+        match self {
+            Tag::Brief => doc.brief = Some(text),
+            Tag::Param => {
+                if let Some(capture) = param_re.captures(&text) {
+                    doc.params.push(entity::Param {
+                        name: capture["name"].to_string(),
+                        description: capture["desc"].to_string(),
+                        direction: capture.name("dir").and_then(|dir| direction(dir.as_str())),
+                    });
+                }
+            }
+            Tag::Return => doc.retval = Some(entity::Return { description: text }),
+            Tag::RetVal => {
+                let (value, description) =
+                    text.split_once(char::is_whitespace).unwrap_or((&text, ""));
+
+                doc.retvals.push(entity::ReturnValue {
+                    value: String::from(value),
+                    description: String::from(description.trim()),
+                });
+            }
+            Tag::Note => doc.notes.push(text),
+            Tag::Warning => doc.warnings.push(text),
+            Tag::See => doc.see.push(text),
+            Tag::Since => doc.since = Some(text),
+            Tag::Deprecated => doc.deprecated = Some(text),
+            Tag::Throws => doc.throws.push(text),
+            Tag::Author => doc.authors.push(text),
+            Tag::Todo => doc.todos.push(text),
+            Tag::Pre => doc.pre.push(text),
+            Tag::Post => doc.post.push(text),
         }
-        use Section::*;
+    }
 
-        let mut curr_section = Description;
+    /// Returns the text of the latest entry for this tag in a doc comment,
+    /// which continuation lines are appended to.
+    fn text_mut(self, doc: &mut entity::DocComment) -> Option<&mut String> {
+        match self {
+            Tag::Brief => doc.brief.as_mut(),
+            Tag::Param => doc.params.last_mut().map(|param| &mut param.description),
+            Tag::Return => doc.retval.as_mut().map(|retval| &mut retval.description),
+            Tag::RetVal => doc.retvals.last_mut().map(|retval| &mut retval.description),
+            Tag::Note => doc.notes.last_mut(),
+            Tag::Warning => doc.warnings.last_mut(),
+            Tag::See => doc.see.last_mut(),
+            Tag::Since => doc.since.as_mut(),
+            Tag::Deprecated => doc.deprecated.as_mut(),
+            Tag::Throws => doc.throws.last_mut(),
+            Tag::Author => doc.authors.last_mut(),
+            Tag::Todo => doc.todos.last_mut(),
+            Tag::Pre => doc.pre.last_mut(),
+            Tag::Post => doc.post.last_mut(),
+        }
+    }
+}
 
-        let param_re = regex::Regex::new(PARAM_PATTERN).unwrap();
-        let retval_re = regex::Regex::new(RETVAL_PATTERN).unwrap();
+/// Matches an inline link in a doc comment's text.
+///
+/// Links may be written as `{@link target}` or `{@link target label}`, or
+/// as `@ref target` or `\ref target`, optionally followed by a quoted label.
+///
+/// Links are replaced at parse time and again by every backend, so the regex
+/// is compiled only once.
+static LINK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(concat!(
+        r"\{@link(?:plain)?\s+(?<link>\w+)(?:\(\))?(?:\s+(?<label>[^}]*?))?\s*\}",
+        r#"|\B[@\\]ref\s+(?<ref>\w+)(?:\(\))?(?:\s+"(?<text>[^"]*)")?"#
+    ))
+    .unwrap()
+});
 
-        let (comment, params, retval) = src
-            .strip_prefix("/**")
-            .unwrap()
-            .strip_suffix("*/")
-            .unwrap()
-            .split(&['\n', '\r'])
-            .map(str::trim)
-            .map(|x| x.trim_start_matches('*'))
-            .map(str::trim_end)
-            .filter(|x| !x.is_empty())
-            .fold(
-                (String::new(), vec![], None),
-                |(mut desc, mut params, mut ret), s| {
-                    if let Some(capture) = param_re.captures(s) {
-                        curr_section = Param;
-
-                        let name = capture["name"].to_string();
-                        let description = capture["desc"].to_string();
-
-                        params.push(entity::Param { name, description });
-                    } else if let Some(capture) = retval_re.captures(s) {
-                        curr_section = RetVal;
-
-                        let description = capture["desc"].to_string();
-
-                        ret = Some(entity::Return { description });
-                    } else {
-                        match curr_section {
-                            Description => {
-                                desc.push_str(s);
-                                desc.push_str("\n");
-                            }
-                            Param => {
-                                let Some(param) = params.last_mut() else {
-                                panic!("Control should not have reached here.");
-                            };
-                                param.description.push(' ');
-                                param.description.push_str(s.trim());
-                            }
-                            RetVal => {
-                                let Some(ref mut retval) = ret else {
-                                panic!("Control should not have reached here.");
-                            };
-                                retval.description.push(' ');
-                                retval.description.push_str(s.trim());
-                            }
-                        }
-                    }
+/// Returns the link matched by [`LINK_RE`].
+fn link_from_capture(capture: &Captures) -> entity::Link {
+    let target = capture.name("link").or_else(|| capture.name("ref"));
+    let label = capture.name("label").or_else(|| capture.name("text"));
 
-                    (desc, params, ret)
-                },
-            );
+    entity::Link {
+        target: target.map_or(String::new(), |x| String::from(x.as_str())),
+        label: label.map(|x| String::from(x.as_str())),
+    }
+}
+
+/// Replaces every inline link in a doc comment's text with the result of `f`.
+pub(crate) fn replace_links(text: &str, mut f: impl FnMut(&entity::Link) -> String) -> String {
+    LINK_RE
+        .replace_all(text, |capture: &Captures| f(&link_from_capture(capture)))
+        .into_owned()
+}
+
+/// Represents a code block being read from a doc comment, opened either by
+/// `@code` or by a markdown fence.
+#[derive(Debug)]
+struct CodeBlock {
+    /// The language of the code, used to tag the generated fence.
+    language: String,
+
+    /// The fence that opened the block, or `None` if it was opened by `@code`.
+    fence: Option<String>,
+
+    /// The lines of the block, with the comment's leading `*` removed.
+    lines: Vec<String>,
+}
+
+impl CodeBlock {
+    /// The pattern of a line that opens a `@code` block.
+    const PATTERN: &'static str = r"^[@\\]code(?:\{\.?(?<lang>[\w+#-]*)\})?\s*$";
+
+    /// Returns a new code block if the line opens one.
+    ///
+    /// `@code` blocks default to C, and take their language from a Doxygen
+    /// file extension as in `@code{.py}`. `code_re` is [`CodeBlock::PATTERN`],
+    /// compiled once by the caller.
+    fn open(line: &str, code_re: &Regex) -> Option<Self> {
+        if let Some(capture) = code_re.captures(line) {
+            let language = capture.name("lang").map_or("c", |lang| lang.as_str());
+
+            return Some(Self {
+                language: String::from(language),
+                fence: None,
+                lines: vec![],
+            });
+        }
+
+        let marker = line.chars().next().filter(|c| *c == '`' || *c == '~')?;
+        let len = line.chars().take_while(|c| *c == marker).count();
+
+        if len < 3 {
+            return None;
+        }
 
         Some(Self {
-            comment,
-            params,
-            retval,
+            language: String::from(line[len..].trim()),
+            fence: Some(line[..len].to_string()),
+            lines: vec![],
         })
     }
+
+    /// Returns whether the line closes this block.
+    fn is_end(&self, line: &str) -> bool {
+        match &self.fence {
+            Some(fence) => line.trim_end() == fence,
+            None => matches!(line.trim_end(), "@endcode" | "\\endcode"),
+        }
+    }
+
+    /// Adds a raw line of the comment to this block, verbatim apart from the
+    /// comment's leading `*`.
+    fn push(&mut self, line: &str) {
+        let text = line.trim_start();
+        let text = match text.strip_prefix('*') {
+            Some(rest) => rest,
+            None => line,
+        };
+
+        self.lines.push(String::from(text.trim_end()));
+    }
+
+    /// Returns this block as a fenced markdown code block.
+    ///
+    /// The common indentation of the lines is removed, as is any blank line
+    /// at the start or end of the block.
+    fn to_markdown(&self) -> String {
+        let indent = self
+            .lines
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+
+        let lines = self
+            .lines
+            .iter()
+            .map(|line| line.get(indent..).unwrap_or(""))
+            .skip_while(|line| line.is_empty())
+            .collect::<Vec<_>>();
+        let end = lines
+            .iter()
+            .rposition(|line| !line.is_empty())
+            .map_or(0, |i| i + 1);
+
+        let fence = self.fence.as_deref().unwrap_or("```");
+        let mut md = format!("{}{}\n", fence, self.language);
+
+        for line in &lines[..end] {
+            md.push_str(line);
+            md.push('\n');
+        }
+
+        md.push_str(fence);
+        md.push('\n');
+
+        md
+    }
+}
+
+impl Parse for entity::DocComment {
+    fn parse(src: &str) -> Result<Self, ParseError> {
+        // Tags may be written Javadoc-style as `@param` or as Doxygen
+        // commands such as `\param`.
+        static TAG_RE: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"^[@\\](?<tag>\w+)\b\s*(?<text>.*)$").unwrap());
+        static PARAM_RE: Lazy<Regex> = Lazy::new(|| Regex::new(Tag::PARAM_PATTERN).unwrap());
+        static CODE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(CodeBlock::PATTERN).unwrap());
+
+        let (tag_re, param_re, code_re) = (&*TAG_RE, &*PARAM_RE, &*CODE_RE);
+
+        let body = src
+            .trim()
+            .strip_prefix("/**")
+            .and_then(|x| x.strip_suffix("*/"))
+            .ok_or_else(|| {
+                ParseError::new(
+                    ParseErrorKind::MalformedComment,
+                    "doc comment must start with `/**` and end with `*/`",
+                )
+            })?;
+
+        let mut doc = Self::default();
+        let mut section = None;
+        let mut code: Option<CodeBlock> = None;
+
+        for line in body.lines() {
+            let s = line.trim().trim_start_matches('*').trim_end();
+
+            if let Some(block) = &mut code {
+                if block.is_end(s.trim_start()) {
+                    doc.comment.push_str(&block.to_markdown());
+                    code = None;
+                } else {
+                    block.push(line);
+                }
+                continue;
+            }
+
+            if let Some(block) = CodeBlock::open(s.trim_start(), code_re) {
+                code = Some(block);
+                section = None;
+                continue;
+            }
+
+            // As in Doxygen, a blank line ends the text of a tag.
+            if s.is_empty() {
+                section = None;
+                continue;
+            }
+
+            let tag = tag_re
+                .captures(s.trim_start())
+                .and_then(|capture| Some((Tag::from_name(&capture["tag"])?, capture)));
+
+            if let Some((tag, capture)) = tag {
+                section = Some(tag);
+                tag.start(&mut doc, &capture["text"], param_re);
+                continue;
+            }
+
+            match section.and_then(|tag| tag.text_mut(&mut doc)) {
+                Some(text) => {
+                    if !text.is_empty() {
+                        text.push(' ');
+                    }
+                    text.push_str(s.trim());
+                }
+                None => {
+                    doc.comment.push_str(s);
+                    doc.comment.push('\n');
+                }
+            }
+        }
+
+        // An unterminated code block runs to the end of the comment.
+        if let Some(block) = code {
+            doc.comment.push_str(&block.to_markdown());
+        }
+
+        doc.links = doc
+            .texts()
+            .into_iter()
+            .flat_map(|text| LINK_RE.captures_iter(text))
+            .map(|capture| link_from_capture(&capture))
+            .collect();
+
+        Ok(doc)
+    }
+}
+
+/// Returns the alias declared after the closing brace of a `typedef` of an
+/// inline struct, union or enum, e.g. `point_t` in `typedef struct { ... } point_t;`.
+fn typedef_alias(src: &str, close: usize) -> Option<String> {
+    if !src.trim_start().starts_with("typedef") {
+        return None;
+    }
+
+    let declarators = split_top_level(src[close..].trim_end_matches(';'), ',');
+    declarator_name(declarators.first()?)
+}
+
+/// Parses the name, members and typedef alias of a struct or union
+/// introduced by `keyword`.
+fn parse_aggregate(
+    keyword: &str,
+    src: &str,
+) -> Result<(String, Vec<entity::Member>, Option<String>), ParseError> {
+    let (src, _) = take_attributes(src);
+    let src = src.as_str();
+    let pat = format!(r"\b{}\b\s*(\w*)\s*\{{", keyword);
+    let capture =
+        get_capture(&pat, src).and_then(|capture| Some((capture.get(0)?, capture.get(1)?)));
+    let Some((whole, name)) = capture else {
+        return Err(malformed(&format!("expected a `{}` body", keyword)));
+    };
+
+    let open = whole.end() - 1;
+    let close = open
+        + group_end(&src[open..], '{', '}')
+            .ok_or_else(|| malformed(&format!("unbalanced braces in `{}`", keyword)))?;
+
+    let name = String::from(name.as_str());
+    let members = split_documented(&src[open + 1..close - 1], ';')
+        .into_iter()
+        .flat_map(|(member, description)| parse_members(&member, description))
+        .collect();
+    let alias = typedef_alias(src, close);
+
+    Ok((name, members, alias))
 }
 
 impl Parse for entity::Struct {
-    fn parse(src: &str) -> Option<Self> {
-        static PAT: &'static str = r"struct\s+(\w+)\s*\{(.*?)\}";
-        let Some(capture) = get_capture(PAT, src) else { return None; };
+    fn parse(src: &str) -> Result<Self, ParseError> {
+        let (name, members, alias) = parse_aggregate("struct", src)?;
 
-        let (_, [name, members]) = capture.extract();
+        Ok(Self {
+            name,
+            alias,
+            members,
+            span: Span::default(),
+        })
+    }
+}
 
-        let name = String::from(name);
-        let members = src_split(members, ';');
+impl Parse for entity::Union {
+    fn parse(src: &str) -> Result<Self, ParseError> {
+        let (name, members, alias) = parse_aggregate("union", src)?;
 
-        Some(Self { name, members })
+        Ok(Self {
+            name,
+            alias,
+            members,
+            span: Span::default(),
+        })
+    }
+}
+
+impl Parse for entity::Member {
+    fn parse(src: &str) -> Result<Self, ParseError> {
+        let (declaration, bitfield) = match split_top_level(src, ':').as_slice() {
+            [declaration, width] => (declaration.clone(), Some(width.clone())),
+            _ => (String::from(src.trim()), None),
+        };
+
+        let Some(open) = declaration.find('{') else {
+            let param = entity::Parameter::parse(&declaration)?;
+
+            return Ok(Self {
+                ty: param.ty,
+                name: param.name,
+                arrays: param.arrays,
+                pointee_arrays: param.pointee_arrays,
+                function: param.function,
+                bitfield,
+                aggregate: None,
+                description: None,
+            });
+        };
+
+        // A member whose struct or union type is defined inline.
+        let close = open
+            + group_end(&declaration[open..], '{', '}')
+                .ok_or_else(|| malformed("unbalanced braces in member"))?;
+        let (_, _, mut ty) = parse_specifiers(&declaration[..open]);
+
+        let aggregate = if ty.name.starts_with("union") {
+            entity::Union::parse(&declaration[..close]).map(entity::Aggregate::Union)
+        } else {
+            entity::Struct::parse(&declaration[..close]).map(entity::Aggregate::Struct)
+        };
+        let aggregate = aggregate.ok().map(Box::new);
+
+        let (declarator, arrays) = split_arrays(&declaration[close..]);
+        let (_, _, mut declared) = parse_specifiers(declarator);
+        let name = declarator_name(declarator);
+        ty.qualifiers.append(&mut declared.qualifiers);
+        ty.pointers = declared.pointers;
+
+        Ok(Self {
+            ty,
+            name,
+            arrays,
+            pointee_arrays: vec![],
+            function: None,
+            bitfield,
+            aggregate,
+            description: None,
+        })
     }
 }
 
 impl Parse for entity::Function {
-    fn parse(src: &str) -> Option<Self> {
-        static PAT: &'static str = r"(\w+)\s+(\w+)\s*\((.*?)\)";
-        let Some(capture) = get_capture(PAT, src) else { return None; };
+    fn parse(src: &str) -> Result<Self, ParseError> {
+        static PAT: &str = r"(\w+)\s*\(";
+        let (text, attributes) = take_attributes(src);
+        let capture =
+            get_capture(PAT, &text).and_then(|capture| Some((capture.get(0)?, capture.get(1)?)));
+        let Some((whole, name)) = capture else {
+            return Err(malformed("expected a function name followed by `(`"));
+        };
 
-        let (_, [return_type, name, params]) = capture.extract();
+        let (prefix, name) = (&text[..whole.start()], name.as_str());
+        let open = whole.end() - 1;
+        let close = open
+            + group_end(&text[open..], '(', ')')
+                .ok_or_else(|| malformed("unbalanced parentheses in function parameters"))?;
+
+        let (storage, specifiers, return_type) = parse_specifiers(prefix);
+
+        if return_type.name.is_empty() {
+            return Err(malformed("expected a return type before the function name"));
+        }
 
         let name = String::from(name);
-        let return_type = String::from(return_type);
-        let params = src_split(params, ',');
+        let params = parse_params(&text[open + 1..close - 1])?;
 
-        Some(Self {
+        Ok(Self {
             name,
+            storage,
+            specifiers,
+            attributes,
             return_type,
             params,
+            span: Span::default(),
+        })
+    }
+}
+
+impl Parse for entity::Parameter {
+    fn parse(src: &str) -> Result<Self, ParseError> {
+        let (text, _) = take_attributes(src);
+        let text = text.trim();
+
+        if text.is_empty() {
+            return Err(malformed("expected a parameter declaration"));
+        }
+
+        // Parentheses after the first `[` are part of an array extent, as in
+        // `int a[sizeof(int)]`, rather than a declarator group.
+        let group = text
+            .find('(')
+            .filter(|&open| text.find('[').map_or(true, |bracket| open < bracket));
+
+        let Some(open) = group else {
+            let (declarator, arrays) = split_arrays(text);
+            let (_, _, mut ty) = parse_specifiers(declarator);
+            let name = take_declarator_name(&mut ty);
+
+            return Ok(Self {
+                ty,
+                name,
+                arrays,
+                pointee_arrays: vec![],
+                function: None,
+            });
+        };
+
+        // A function pointer, e.g. `void (*cb)(int)`, or a pointer to an
+        // array, e.g. `int (*mat)[4]`.
+        let unbalanced = || malformed("unbalanced parentheses in declarator");
+        let close = open + group_end(&text[open..], '(', ')').ok_or_else(unbalanced)?;
+        let rest = text[close..].trim_start();
+
+        let (pointee_arrays, function) = if rest.starts_with('[') {
+            let (suffix, pointee_arrays) = split_arrays(rest);
+
+            if !suffix.is_empty() {
+                return Err(malformed("expected array extents after declarator"));
+            }
+
+            (pointee_arrays, None)
+        } else if rest.starts_with('(') {
+            let end = group_end(rest, '(', ')').ok_or_else(unbalanced)?;
+            (vec![], Some(parse_params(&rest[1..end - 1])?))
+        } else {
+            return Err(malformed(
+                "expected parameters or array extents after declarator",
+            ));
+        };
+
+        let (_, _, ty) = parse_specifiers(&text[..open]);
+        let (declarator, arrays) = split_arrays(&text[open + 1..close - 1]);
+        let name = declarator_name(declarator);
+
+        Ok(Self {
+            ty,
+            name,
+            arrays,
+            pointee_arrays,
+            function,
         })
     }
 }
 
 impl Parse for entity::Enum {
-    fn parse(src: &str) -> Option<Self> {
-        static PAT: &'static str = r"enum\s+(\w+)\s*\{(.*?)\}";
-        let Some(capture) = get_capture(PAT, src) else { return None; };
+    fn parse(src: &str) -> Result<Self, ParseError> {
+        static PAT: &str = r"\benum\b\s*(\w*)\s*\{";
+        let (src, _) = take_attributes(src);
+        let src = src.as_str();
+        let capture =
+            get_capture(PAT, src).and_then(|capture| Some((capture.get(0)?, capture.get(1)?)));
+        let Some((whole, name)) = capture else {
+            return Err(malformed("expected an `enum` body"));
+        };
 
-        let (_, [name, variants]) = capture.extract();
+        let open = whole.end() - 1;
+        let close = open
+            + group_end(&src[open..], '{', '}')
+                .ok_or_else(|| malformed("unbalanced braces in `enum`"))?;
 
-        let name = String::from(name);
-        let variants = src_split(variants, ',');
+        let name = String::from(name.as_str());
+        let alias = typedef_alias(src, close);
+        let variants = split_documented(&src[open + 1..close - 1], ',')
+            .into_iter()
+            .filter_map(|(variant, description)| {
+                let mut variant = entity::Variant::parse(&variant).ok()?;
+                variant.description = description;
+                Some(variant)
+            })
+            .collect();
+
+        Ok(Self {
+            name,
+            alias,
+            variants,
+            span: Span::default(),
+        })
+    }
+}
+
+impl Parse for entity::Typedef {
+    fn parse(src: &str) -> Result<Self, ParseError> {
+        let declaration = src
+            .trim()
+            .strip_prefix("typedef")
+            .ok_or_else(|| malformed("expected `typedef`"))?
+            .trim_end_matches(';');
+        let declarators = split_top_level(declaration, ',');
+        let target = entity::Parameter::parse(declarators.first().map_or("", String::as_str))?;
+
+        Ok(Self {
+            name: target.name.ok_or_else(|| {
+                ParseError::new(ParseErrorKind::MissingName, "typedef declares no name")
+            })?,
+            ty: target.ty,
+            arrays: target.arrays,
+            pointee_arrays: target.pointee_arrays,
+            function: target.function,
+            span: Span::default(),
+        })
+    }
+}
+
+impl Parse for entity::Macro {
+    fn parse(src: &str) -> Result<Self, ParseError> {
+        static PAT: &str = r"#\s*define\s+(\w+)(\([^)]*\))?(.*)";
+        let Some(capture) = get_capture(PAT, src) else {
+            return Err(malformed("expected `#define` followed by a name"));
+        };
+
+        let name = String::from(&capture[1]);
+        let params = capture
+            .get(2)
+            .map(|params| split_top_level(params.as_str().trim_matches(['(', ')']), ','));
+        let value = capture[3].split_whitespace().collect::<Vec<_>>().join(" ");
+
+        Ok(Self {
+            name,
+            params,
+            value,
+            span: Span::default(),
+        })
+    }
+}
+
+impl Parse for entity::Variable {
+    fn parse(src: &str) -> Result<Self, ParseError> {
+        let declarators = split_top_level(src.trim().trim_end_matches(';'), ',');
+        let first = declarators.first().map_or("", String::as_str);
+        let (declarator, initializer) = match first.split_once('=') {
+            Some((declarator, initializer)) => (declarator, Some(initializer)),
+            None => (first, None),
+        };
+
+        let (storage, _, _) = parse_specifiers(&take_attributes(declarator).0);
+        let declaration = entity::Parameter::parse(declarator)?;
+        let initializer =
+            initializer.map(|value| value.split_whitespace().collect::<Vec<_>>().join(" "));
+
+        Ok(Self {
+            name: declaration.name.ok_or_else(|| {
+                ParseError::new(ParseErrorKind::MissingName, "declaration declares no name")
+            })?,
+            storage,
+            ty: declaration.ty,
+            arrays: declaration.arrays,
+            pointee_arrays: declaration.pointee_arrays,
+            function: declaration.function,
+            initializer,
+            span: Span::default(),
+        })
+    }
+}
+
+impl Parse for entity::Variant {
+    fn parse(src: &str) -> Result<Self, ParseError> {
+        let (name, value) = match src.split_once('=') {
+            Some((name, value)) => (name, Some(value.split_whitespace().collect::<Vec<_>>())),
+            None => (src, None),
+        };
+
+        let name = String::from(name.trim());
+
+        if name.is_empty() {
+            return Err(ParseError::new(
+                ParseErrorKind::MissingName,
+                "enum variant has no name",
+            ));
+        }
+
+        Ok(Self {
+            name,
+            value: value.map(|value| value.join(" ")),
+            description: None,
+        })
+    }
+}
+
+/// Parses a single token into a ParsedToken. A variable token only yields
+/// its first declarator; see [`parse_tokens`] for the others.
+fn parse_token(pair: &TokenValuePair) -> Result<ParsedToken, ParseError> {
+    let value = pair.value.as_str();
 
-        Some(Self { name, variants })
+    match pair.token {
+        token::DocComment => entity::DocComment::parse(value).map(ParsedToken::DocComment),
+        token::Enum => entity::Enum::parse(value).map(ParsedToken::Enum),
+        token::Function => entity::Function::parse(value).map(ParsedToken::Function),
+        token::Struct => entity::Struct::parse(value).map(ParsedToken::Struct),
+        token::Union => entity::Union::parse(value).map(ParsedToken::Union),
+        token::Typedef => entity::Typedef::parse(value).map(ParsedToken::Typedef),
+        token::Macro => entity::Macro::parse(value).map(ParsedToken::Macro),
+        token::Variable => entity::Variable::parse(value).map(ParsedToken::Variable),
+        token::Unknown => Ok(ParsedToken::Unknown(pair.span)),
     }
 }
 
 /// Parses a vector of TokenValuePairs into a vector of ParsedTokens.
-pub fn parse_tokens(pairs: &Vec<TokenValuePair>) -> Vec<ParsedToken> {
-    pairs
-        .iter()
-        .filter_map(|pair| {
-            Some(match pair.token {
-                token::DocComment => {
-                    ParsedToken::DocComment(entity::DocComment::parse(&pair.value).unwrap())
-                }
-                token::Enum => ParsedToken::Enum(entity::Enum::parse(&pair.value).unwrap()),
-                token::Function => {
-                    ParsedToken::Function(entity::Function::parse(&pair.value).unwrap())
+///
+/// A token that fails to parse does not stop the run: its error is collected,
+/// with the span of the token, and parsing carries on with the next token.
+/// A declaration the tokenizer did not recognize is reported the same way,
+/// and kept as [`ParsedToken::Unknown`].
+///
+/// Errors are marked [`documented`](ParseError::documented) if they are in a
+/// doc comment or in the declaration right after one.
+///
+/// A variable token declaring several variables, such as `int a, b;`, yields
+/// one ParsedToken per variable, all with the span of the token.
+///
+/// Forward declarations such as `struct node;` are tokenized as variables,
+/// but have no variable name, and are skipped without an error.
+pub fn parse_tokens(pairs: &[TokenValuePair]) -> (Vec<ParsedToken>, Vec<ParseError>) {
+    let mut parsed = vec![];
+    let mut errors = vec![];
+    let mut commented = false;
+
+    for pair in pairs {
+        let is_comment = matches!(pair.token, token::DocComment);
+        let documented = is_comment || commented;
+        commented = is_comment;
+
+        if matches!(pair.token, token::Unknown) {
+            errors.push(
+                ParseError::new(ParseErrorKind::UnknownToken, "unrecognized declaration")
+                    .with_span(pair.span)
+                    .with_documented(documented),
+            );
+        }
+
+        let result = match pair.token {
+            token::Variable => parse_variables(&pair.value)
+                .map(|variables| variables.into_iter().map(ParsedToken::Variable).collect()),
+            _ => parse_token(pair).map(|token| vec![token]),
+        };
+
+        match result {
+            Ok(tokens) => {
+                for mut token in tokens {
+                    token.set_span(pair.span);
+                    parsed.push(token);
                 }
-                token::Struct => ParsedToken::Struct(entity::Struct::parse(&pair.value).unwrap()),
-            })
-        })
-        .collect()
+            }
+            Err(error)
+                if matches!(pair.token, token::Variable)
+                    && error.kind == ParseErrorKind::MissingName => {}
+            Err(error) => errors.push(error.with_span(pair.span).with_documented(documented)),
+        }
+    }
+
+    (parsed, errors)
 }
@@ -0,0 +1,55 @@
+//! # Span Module
+//!
+//! This module defines source locations. Spans are produced by the lexer and
+//! carried through tokens, entities and AST nodes, so that generated
+//! documentation and diagnostics can refer back to the source.
+
+/// Represents a region of source code.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// The byte offset of the start of the region.
+    pub start: usize,
+
+    /// The byte offset just past the end of the region.
+    pub end: usize,
+
+    /// The 1-based line number of the start of the region.
+    pub line: usize,
+
+    /// The 1-based column number, in characters, of the start of the region.
+    pub column: usize,
+}
+
+/// Maps byte offsets in a source string to line and column numbers.
+#[derive(Debug)]
+pub(crate) struct LineIndex<'a> {
+    /// The indexed source.
+    src: &'a str,
+
+    /// The byte offset of the start of each line.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Creates a new index over the given source.
+    pub(crate) fn new(src: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self { src, line_starts }
+    }
+
+    /// Returns the span covering the given byte range.
+    pub(crate) fn span(&self, start: usize, end: usize) -> Span {
+        let line = self.line_starts.partition_point(|&x| x <= start);
+        let column = self.src[self.line_starts[line - 1]..start].chars().count() + 1;
+
+        Span {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+}
@@ -0,0 +1,539 @@
+//! # Template Module
+//!
+//! This module renders documentation through templates, so a project can
+//! change the headings, wording and layout of its documentation without
+//! changing doc-buildr. Templates use a small subset of the Mustache syntax:
+//!
+//! - `{{name}}` is replaced by the value of `name`, looked up in the current
+//!   section first and then in the enclosing ones. `{{doc.brief}}` looks up
+//!   `brief` within `doc`, and `{{.}}` is the value of the current section.
+//! - `{{name|filter}}` passes the value through a filter: `table` escapes
+//!   pipes for a markdown table cell, `html` escapes HTML, and `upper` and
+//!   `lower` change its case.
+//! - `{{#name}}...{{/name}}` renders its contents once for every item of a
+//!   list, or once if the value is a non-empty text, `true` or an object.
+//!   Within a list, `first`, `last` and `index` describe the current item.
+//! - `{{^name}}...{{/name}}` renders its contents if the value is missing,
+//!   empty or `false`.
+//! - `{{> name}}` renders the template called `name` in place.
+//! - `{{! comment }}` is ignored.
+//!
+//! A line holding nothing but a section, partial or comment tag is removed
+//! from the output entirely, along with its line break, so tags can go on
+//! lines of their own. A single line break at the end of a template is
+//! ignored.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::generator::html_escape;
+use crate::md_gen::table_escape;
+
+/// The deepest partials may be nested before rendering stops, so a template
+/// that includes itself cannot recurse forever.
+const MAX_DEPTH: usize = 64;
+
+/// The templates doc-buildr ships with, which render markdown.
+const BUILTIN: &[(&str, &str)] = &[
+    ("module", include_str!("../templates/module.md")),
+    ("function", include_str!("../templates/function.md")),
+    ("struct", include_str!("../templates/struct.md")),
+    ("union", include_str!("../templates/union.md")),
+    ("enum", include_str!("../templates/enum.md")),
+    ("typedef", include_str!("../templates/typedef.md")),
+    ("macro", include_str!("../templates/macro.md")),
+    ("variable", include_str!("../templates/variable.md")),
+    ("description", include_str!("../templates/description.md")),
+    ("tag", include_str!("../templates/tag.md")),
+    ("params", include_str!("../templates/params.md")),
+    ("retvals", include_str!("../templates/retvals.md")),
+    ("members", include_str!("../templates/members.md")),
+    ("location", include_str!("../templates/location.md")),
+];
+
+/// Represents a value templates can refer to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Text(String),
+    Bool(bool),
+    List(Vec<Value>),
+    Map(Vec<(&'static str, Value)>),
+}
+
+impl Value {
+    /// Creates a text value.
+    pub fn text(s: &str) -> Self {
+        Value::Text(String::from(s))
+    }
+
+    /// Returns whether a section over this value renders its contents.
+    fn is_truthy(&self) -> bool {
+        match self {
+            Value::Text(s) => !s.is_empty(),
+            Value::Bool(b) => *b,
+            Value::List(items) => !items.is_empty(),
+            Value::Map(_) => true,
+        }
+    }
+
+    /// Returns the field of an object with the given key, if any.
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Map(fields) => fields.iter().find(|(k, _)| *k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Returns this value as it is written into a template.
+    fn to_text(&self) -> Cow<'_, str> {
+        match self {
+            Value::Text(s) => Cow::Borrowed(s),
+            Value::Bool(b) => Cow::Owned(b.to_string()),
+            Value::List(_) | Value::Map(_) => Cow::Borrowed(""),
+        }
+    }
+}
+
+/// Represents an error in a template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    /// The name of the template.
+    pub name: String,
+
+    /// The line of the template the error is on, counting from 1, or 0 if
+    /// the error is not on a particular line.
+    pub line: usize,
+
+    /// A human-readable description of the error.
+    pub message: String,
+}
+
+impl TemplateError {
+    /// Creates a new error in the named template.
+    fn new(name: &str, line: usize, message: &str) -> Self {
+        Self {
+            name: String::from(name),
+            line,
+            message: String::from(message),
+        }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            0 => write!(f, "{}: {}", self.name, self.message),
+            line => write!(f, "{}:{}: {}", self.name, line, self.message),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// Represents the filters a value can be passed through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Filter {
+    Table,
+    Html,
+    Upper,
+    Lower,
+}
+
+impl Filter {
+    /// Returns the filter with the given name, if there is one.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "table" => Some(Filter::Table),
+            "html" => Some(Filter::Html),
+            "upper" => Some(Filter::Upper),
+            "lower" => Some(Filter::Lower),
+            _ => None,
+        }
+    }
+
+    /// Applies the filter to a text.
+    fn apply(&self, text: &str) -> String {
+        match self {
+            Filter::Table => table_escape(text),
+            Filter::Html => html_escape(text),
+            Filter::Upper => text.to_uppercase(),
+            Filter::Lower => text.to_lowercase(),
+        }
+    }
+}
+
+/// Represents a part of a parsed template.
+#[derive(Debug, Clone)]
+enum Part {
+    /// Text copied to the output as is.
+    Text(String),
+
+    /// A value, given by its path, optionally passed through a filter.
+    Var(Vec<String>, Option<Filter>),
+
+    /// A section, given by its path, and whether it is inverted.
+    Section(Vec<String>, bool, Vec<Part>),
+
+    /// Another template, rendered in place.
+    Partial(String),
+}
+
+/// Represents a tag, as found while scanning a template.
+enum Tag<'t> {
+    Var(&'t str),
+    Open(&'t str, bool),
+    Close(&'t str),
+    Partial(&'t str),
+    Comment,
+}
+
+impl<'t> Tag<'t> {
+    /// Classifies the contents of a tag.
+    fn from(tag: &'t str) -> Self {
+        let tag = tag.trim();
+        let rest = tag.get(1..).unwrap_or("").trim();
+
+        match tag.chars().next() {
+            Some('#') => Tag::Open(rest, false),
+            Some('^') => Tag::Open(rest, true),
+            Some('/') => Tag::Close(rest),
+            Some('>') => Tag::Partial(rest),
+            Some('!') => Tag::Comment,
+            _ => Tag::Var(tag),
+        }
+    }
+
+    /// Returns whether the tag may stand on a line of its own, in which case
+    /// the line is removed from the output.
+    fn is_standalone(&self) -> bool {
+        !matches!(self, Tag::Var(_))
+    }
+}
+
+/// Splits a path such as `doc.brief` into its segments. The path `.` refers
+/// to the current section and has no segments.
+fn path(name: &str) -> Vec<String> {
+    match name {
+        "." => vec![],
+        _ => name.split('.').map(String::from).collect(),
+    }
+}
+
+/// Represents a parsed template.
+#[derive(Debug, Clone)]
+struct Template {
+    /// The parts of the template, in order.
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parses the source of the named template.
+    fn parse(name: &str, src: &str) -> Result<Self, TemplateError> {
+        let src = src.strip_suffix('\n').unwrap_or(src);
+
+        // The open sections, each with its name, the line it was opened on,
+        // whether it is inverted and the parts before it.
+        let mut stack: Vec<(&str, usize, bool, Vec<Part>)> = vec![];
+        let mut parts = vec![];
+
+        for (i, line) in src.split_inclusive('\n').enumerate() {
+            let number = i + 1;
+            let mut pieces: Vec<Result<&str, Tag>> = vec![];
+            let mut rest = line;
+
+            while let Some(start) = rest.find("{{") {
+                let Some(end) = rest[start..].find("}}") else {
+                    return Err(TemplateError::new(name, number, "unclosed tag"));
+                };
+
+                pieces.push(Ok(&rest[..start]));
+                pieces.push(Err(Tag::from(&rest[start + 2..start + end])));
+                rest = &rest[start + end + 2..];
+            }
+
+            pieces.push(Ok(rest));
+
+            let tags = pieces.iter().filter(|piece| piece.is_err()).count();
+            let standalone = tags == 1
+                && pieces.iter().all(|piece| match piece {
+                    Ok(text) => text.trim().is_empty(),
+                    Err(tag) => tag.is_standalone(),
+                });
+
+            for piece in pieces {
+                match piece {
+                    Ok(_) if standalone => {}
+                    Ok("") => {}
+                    Ok(text) => parts.push(Part::Text(String::from(text))),
+                    Err(Tag::Var(var)) => {
+                        let (var, filter) = match var.split_once('|') {
+                            Some((var, filter)) => match Filter::from_name(filter.trim()) {
+                                Some(filter) => (var.trim(), Some(filter)),
+                                None => {
+                                    let message = format!("unknown filter `{}`", filter.trim());
+                                    return Err(TemplateError::new(name, number, &message));
+                                }
+                            },
+                            None => (var, None),
+                        };
+
+                        parts.push(Part::Var(path(var), filter));
+                    }
+                    Err(Tag::Open(section, inverted)) => {
+                        stack.push((section, number, inverted, std::mem::take(&mut parts)));
+                    }
+                    Err(Tag::Close(section)) => match stack.pop() {
+                        Some((open, _, inverted, before)) if open == section => {
+                            let children = std::mem::replace(&mut parts, before);
+                            parts.push(Part::Section(path(section), inverted, children));
+                        }
+                        Some((open, ..)) => {
+                            let message = format!("`{}` closed before `{}`", section, open);
+                            return Err(TemplateError::new(name, number, &message));
+                        }
+                        None => {
+                            let message = format!("`{}` closed but never opened", section);
+                            return Err(TemplateError::new(name, number, &message));
+                        }
+                    },
+                    Err(Tag::Partial(partial)) => parts.push(Part::Partial(String::from(partial))),
+                    Err(Tag::Comment) => {}
+                }
+            }
+        }
+
+        if let Some((open, number, ..)) = stack.pop() {
+            let message = format!("`{}` is never closed", open);
+            return Err(TemplateError::new(name, number, &message));
+        }
+
+        Ok(Self { parts })
+    }
+
+    /// Returns the names of the templates this one renders in place.
+    fn partials(&self) -> Vec<&str> {
+        fn collect<'p>(parts: &'p [Part], names: &mut Vec<&'p str>) {
+            for part in parts {
+                match part {
+                    Part::Partial(name) => names.push(name),
+                    Part::Section(_, _, children) => collect(children, names),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut names = vec![];
+        collect(&self.parts, &mut names);
+        names
+    }
+}
+
+/// Represents a section being rendered.
+#[derive(Clone, Copy)]
+enum Frame<'v> {
+    /// A section over a value.
+    Value(&'v Value),
+
+    /// The position of the current item of a list.
+    Item(usize, usize),
+}
+
+impl<'v> Frame<'v> {
+    /// Returns the value of a name within this frame, if it has one.
+    fn get(&self, key: &str) -> Option<Cow<'v, Value>> {
+        match self {
+            Frame::Value(value) => value.get(key).map(Cow::Borrowed),
+            Frame::Item(index, len) => match key {
+                "first" => Some(Cow::Owned(Value::Bool(*index == 0))),
+                "last" => Some(Cow::Owned(Value::Bool(index + 1 == *len))),
+                "index" => Some(Cow::Owned(Value::Text(index.to_string()))),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Represents a set of named templates, which may render one another.
+#[derive(Debug, Clone)]
+pub struct Templates {
+    /// The templates by name.
+    templates: HashMap<String, Template>,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Templates {
+    /// Returns the templates doc-buildr ships with, which render markdown.
+    pub fn builtin() -> Self {
+        let templates = BUILTIN
+            .iter()
+            .map(|(name, src)| (String::from(*name), Template::parse(name, src).unwrap()))
+            .collect();
+
+        Self { templates }
+    }
+
+    /// Returns the built-in templates, overridden by the templates in a
+    /// directory.
+    ///
+    /// Every `.md` file in the directory is a template named after its stem,
+    /// so `function.md` replaces the template for functions, and files with
+    /// other names can be rendered as partials.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the templates if successful, or a
+    /// `TemplateError` if a template cannot be read or parsed, or renders a
+    /// template that does not exist.
+    pub fn from_dir(dir: &Path) -> Result<Self, TemplateError> {
+        let unreadable = |name: &str| TemplateError::new(name, 0, "cannot be read");
+        let mut templates = Self::builtin();
+
+        let entries = fs::read_dir(dir).map_err(|_| unreadable(&dir.display().to_string()))?;
+
+        for entry in entries {
+            let path = entry
+                .map_err(|_| unreadable(&dir.display().to_string()))?
+                .path();
+
+            if path.extension().and_then(|x| x.to_str()) != Some("md") {
+                continue;
+            }
+
+            let Some(name) = path.file_stem().and_then(|x| x.to_str()) else {
+                continue;
+            };
+
+            let display = path.display().to_string();
+            let src = fs::read_to_string(&path).map_err(|_| unreadable(&display))?;
+            let template = Template::parse(&display, &src)?;
+
+            templates.templates.insert(String::from(name), template);
+        }
+
+        for (name, template) in &templates.templates {
+            if let Some(missing) = template
+                .partials()
+                .into_iter()
+                .find(|partial| !templates.templates.contains_key(*partial))
+            {
+                let message = format!("no template named `{}`", missing);
+                return Err(TemplateError::new(name, 0, &message));
+            }
+        }
+
+        Ok(templates)
+    }
+
+    /// Renders the named template with the given value as its context.
+    ///
+    /// Nothing is rendered if there is no template with that name.
+    pub fn render(&self, name: &str, context: &Value) -> String {
+        let mut out = String::new();
+        let mut stack = vec![Frame::Value(context)];
+
+        if let Some(template) = self.templates.get(name) {
+            self.render_parts(&template.parts, &mut stack, &mut out, 0);
+        }
+
+        out
+    }
+
+    /// Renders the parts of a template with the given sections open.
+    fn render_parts<'v>(
+        &'v self,
+        parts: &'v [Part],
+        stack: &mut Vec<Frame<'v>>,
+        out: &mut String,
+        depth: usize,
+    ) {
+        for part in parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Var(path, filter) => {
+                    if let Some(value) = lookup(stack, path) {
+                        match filter {
+                            Some(filter) => out.push_str(&filter.apply(&value.to_text())),
+                            None => out.push_str(&value.to_text()),
+                        }
+                    }
+                }
+                Part::Section(path, inverted, children) => {
+                    let value = lookup(stack, path).filter(|value| value.is_truthy());
+
+                    match value {
+                        None if *inverted => self.render_parts(children, stack, out, depth),
+                        Some(value) if !inverted => {
+                            self.render_section(&value, children, stack, out, depth)
+                        }
+                        _ => {}
+                    }
+                }
+                Part::Partial(name) => {
+                    if let Some(template) = self.templates.get(name).filter(|_| depth < MAX_DEPTH) {
+                        self.render_parts(&template.parts, stack, out, depth + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Renders the contents of a section over a truthy value.
+    fn render_section<'s>(
+        &'s self,
+        value: &'s Value,
+        children: &'s [Part],
+        stack: &[Frame<'s>],
+        out: &mut String,
+        depth: usize,
+    ) {
+        // The value may live shorter than the sections around it, so the
+        // contents are rendered with a copy of the stack.
+        let mut stack = stack.to_vec();
+
+        match value {
+            Value::List(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    stack.push(Frame::Item(i, items.len()));
+                    stack.push(Frame::Value(item));
+                    self.render_parts(children, &mut stack, out, depth);
+                    stack.truncate(stack.len() - 2);
+                }
+            }
+            Value::Bool(_) => self.render_parts(children, &mut stack, out, depth),
+            _ => {
+                stack.push(Frame::Value(value));
+                self.render_parts(children, &mut stack, out, depth);
+            }
+        }
+    }
+}
+
+/// Looks up a path, resolving its first segment in the innermost section
+/// that has it and the rest within the value found.
+fn lookup<'v>(stack: &[Frame<'v>], path: &[String]) -> Option<Cow<'v, Value>> {
+    let Some((first, rest)) = path.split_first() else {
+        return stack.iter().rev().find_map(|frame| match frame {
+            Frame::Value(value) => Some(Cow::Borrowed(*value)),
+            Frame::Item(..) => None,
+        });
+    };
+
+    let mut value = stack.iter().rev().find_map(|frame| frame.get(first))?;
+
+    for key in rest {
+        value = match value {
+            Cow::Borrowed(value) => Cow::Borrowed(value.get(key)?),
+            Cow::Owned(value) => Cow::Owned(value.get(key)?.clone()),
+        };
+    }
+
+    Some(value)
+}
@@ -2,8 +2,16 @@
 //!
 //! This module provides a tokenization system for parsing specific structures in code,
 //! particularly focused on C-style syntax elements. It offers functionality to identify
-//! and extract doc comments, functions, structs, and enums from a given code string.
+//! and extract doc comments, functions, structs, unions, enums, typedefs,
+//! macros and global variables from a given code string.
+//!
+//! The source is first split into doc comments and top-level declarations by the
+//! [`Lexer`](crate::lexer::Lexer), and each declaration is then classified using
+//! the token patterns below.
 
+use crate::error::{ParseError, ParseErrorKind};
+use crate::lexer::{LexemeKind, Lexer};
+use crate::span::Span;
 use regex::*;
 
 /// Represents the types of tokens that can be identified.
@@ -12,14 +20,30 @@ pub enum Token {
     /// Represents a documentation comment.
     DocComment,
 
-    /// Represents a function declaration.
+    /// Represents a function declaration or definition.
     Function,
 
     /// Represents a struct declaration.
     Struct,
 
+    /// Represents a union declaration.
+    Union,
+
     /// Represents a enum declaration.
     Enum,
+
+    /// Represents a typedef of a type other than an inline struct, union or enum.
+    Typedef,
+
+    /// Represents a `#define` macro.
+    Macro,
+
+    /// Represents a global variable or constant declaration.
+    Variable,
+
+    /// Represents a declaration that matches none of the other tokens, such
+    /// as `_Static_assert(sizeof(int) == 4, "int");`.
+    Unknown,
 }
 
 /// A struct holding a token name and its corresponding regex pattern.
@@ -34,33 +58,72 @@ pub struct TokenValuePair {
 
     /// The actual text content of the matched token.
     pub value: String,
+
+    /// The location of the matched token in the source.
+    pub span: Span,
 }
 
 impl TokenValuePair {
     /// Creates a `TokenValuePair` from a regex `Captures` object.
-    pub fn from_capture(capture: &Captures) -> Self {
+    ///
+    /// The span of the returned pair is empty; the caller knows where the
+    /// captured text came from and is expected to fill it in.
+    ///
+    /// Returns an error if the capture has no group named after a token.
+    pub fn from_capture(capture: &Captures) -> Result<Self, ParseError> {
         if capture.name("DocComment").is_some() {
-            Self {
+            Ok(Self {
                 token: DocComment,
                 value: String::from(&capture["DocComment"]),
-            }
+                span: Span::default(),
+            })
         } else if capture.name("Function").is_some() {
-            Self {
+            Ok(Self {
                 token: Function,
                 value: String::from(&capture["Function"]),
-            }
+                span: Span::default(),
+            })
         } else if capture.name("Struct").is_some() {
-            Self {
+            Ok(Self {
                 token: Struct,
                 value: String::from(&capture["Struct"]),
-            }
+                span: Span::default(),
+            })
+        } else if capture.name("Union").is_some() {
+            Ok(Self {
+                token: Union,
+                value: String::from(&capture["Union"]),
+                span: Span::default(),
+            })
         } else if capture.name("Enum").is_some() {
-            Self {
+            Ok(Self {
                 token: Enum,
                 value: String::from(&capture["Enum"]),
-            }
+                span: Span::default(),
+            })
+        } else if capture.name("Typedef").is_some() {
+            Ok(Self {
+                token: Typedef,
+                value: String::from(&capture["Typedef"]),
+                span: Span::default(),
+            })
+        } else if capture.name("Macro").is_some() {
+            Ok(Self {
+                token: Macro,
+                value: String::from(&capture["Macro"]),
+                span: Span::default(),
+            })
+        } else if capture.name("Variable").is_some() {
+            Ok(Self {
+                token: Variable,
+                value: String::from(&capture["Variable"]),
+                span: Span::default(),
+            })
         } else {
-            panic!("Expected named field!");
+            Err(ParseError::new(
+                ParseErrorKind::UnknownToken,
+                "expected a capture named after a token",
+            ))
         }
     }
 }
@@ -69,11 +132,15 @@ pub use Token::*;
 impl<'a> Token {
     /// Returns a static array of `TokenPatternPair`s for all token types.
     fn get_pairs() -> &'static [TokenPatternPair] {
-        static NAMES: [TokenPatternPair; 4] = [
+        static NAMES: [TokenPatternPair; 8] = [
             TokenPatternPair(DocComment.name(), DocComment.as_str()),
+            TokenPatternPair(Typedef.name(), Typedef.as_str()),
             TokenPatternPair(Function.name(), Function.as_str()),
             TokenPatternPair(Struct.name(), Struct.as_str()),
+            TokenPatternPair(Union.name(), Union.as_str()),
             TokenPatternPair(Enum.name(), Enum.as_str()),
+            TokenPatternPair(Macro.name(), Macro.as_str()),
+            TokenPatternPair(Variable.name(), Variable.as_str()),
         ];
 
         &NAMES
@@ -85,7 +152,12 @@ impl<'a> Token {
             Token::DocComment => "DocComment",
             Token::Function => "Function",
             Token::Struct => "Struct",
+            Token::Union => "Union",
             Token::Enum => "Enum",
+            Token::Typedef => "Typedef",
+            Token::Macro => "Macro",
+            Token::Variable => "Variable",
+            Token::Unknown => "Unknown",
         }
     }
 
@@ -93,19 +165,56 @@ impl<'a> Token {
     pub const fn as_str(&self) -> &'static str {
         match self {
             Token::DocComment => r"/\*\*(.*?)\*/",
-            Token::Function => r"\w+\s+\w+\s*\([^)]*\)\s*;",
-            Token::Struct => r"(typedef)?struct\s+\w+\s*\{[^}]*\}\s*(\w+)?;",
-            Token::Enum => r"(typedef)?enum\s+\w+\s*\{[^}]*\}\s*(\w+)?;",
+            Token::Function => concat!(
+                r"(?:(?:\w+|\*|__attribute__\s*\(\((?:[^()]|\([^()]*\))*\)\)|\[\[[^\]]*\]\])\s*)*?",
+                r"(?:\w+\s+|\*\s*|__attribute__\s*\(\((?:[^()]|\([^()]*\))*\)\)\s*|\[\[[^\]]*\]\]\s*)",
+                r"\w+\s*\((?:[^()]|\((?:[^()]|\([^()]*\))*\))*\)\s*",
+                r"(?:__attribute__\s*\(\((?:[^()]|\([^()]*\))*\)\)\s*)*(?:;|\{\})",
+            ),
+            Token::Struct => concat!(
+                r"(?:typedef\s+struct",
+                r"(?:\s*__attribute__\s*\(\((?:[^()]|\([^()]*\))*\)\))*",
+                r"\s*\w*|struct",
+                r"(?:\s*__attribute__\s*\(\((?:[^()]|\([^()]*\))*\)\))*",
+                r"(?:\s+\w+)?)\s*\{.*\}[\w\s,\*]*;",
+            ),
+            Token::Union => concat!(
+                r"(?:typedef\s+union",
+                r"(?:\s*__attribute__\s*\(\((?:[^()]|\([^()]*\))*\)\))*",
+                r"\s*\w*|union",
+                r"(?:\s*__attribute__\s*\(\((?:[^()]|\([^()]*\))*\)\))*",
+                r"(?:\s+\w+)?)\s*\{.*\}[\w\s,\*]*;",
+            ),
+            Token::Enum => concat!(
+                r"(?:typedef\s+enum",
+                r"(?:\s*__attribute__\s*\(\((?:[^()]|\([^()]*\))*\)\))*",
+                r"\s*\w*|enum",
+                r"(?:\s*__attribute__\s*\(\((?:[^()]|\([^()]*\))*\)\))*",
+                r"(?:\s+\w+)?)\s*\{.*\}[\w\s,\*]*;",
+            ),
+            Token::Macro => r"#define\s+\w+.*",
+            Token::Variable => concat!(
+                r"\w+(?:\s+|\s*\*+\s*)",
+                r"(?:\w+|\*|\s+|,|\[[^\]]*\]|\(\s*\*+\s*\w+\s*(?:\[[^\]]*\]\s*)*\)\s*\((?:[^()]|\([^()]*\))*\)",
+                r"|\(\s*\*+\s*\w+\s*\)\s*(?:\[[^\]]*\]\s*)+)+",
+                r"(?:=.*)?;",
+            ),
+            Token::Typedef => r"typedef\s+(?:[^{}();]*|[^{}();]*\(\s*\*[^{};]*);",
+            Token::Unknown => r".*",
         }
     }
 
     /// Constructs and returns a `Regex` object that can match all token types.
+    ///
+    /// The regex is anchored to both ends of the input, so it only matches a
+    /// token that spans a whole lexed declaration.
     pub fn get_regex() -> Regex {
         let pattern = Self::get_pairs()
             .iter()
             .map(|x| format!("(?<{}>{})", x.0, x.1))
             .collect::<Vec<_>>()
             .join("|");
+        let pattern = format!(r"\A(?:{})\z", pattern);
 
         RegexBuilder::new(&pattern)
             .multi_line(true)
@@ -115,10 +224,40 @@ impl<'a> Token {
     }
 
     /// Tokenizes the given code string and returns a vector of `TokenValuePair`s.
+    ///
+    /// A declaration that matches none of the token patterns is returned as
+    /// an [`Unknown`] token rather than dropped, so that the doc comment in
+    /// front of it is not attached to the next declaration.
     pub fn tokenize(code: &'a str) -> Vec<TokenValuePair> {
-        Token::get_regex()
-            .captures_iter(code)
-            .map(|capture| TokenValuePair::from_capture(&capture))
+        let re = Token::get_regex();
+
+        Lexer::new(code)
+            .map(|lexeme| match lexeme.kind {
+                LexemeKind::DocComment => TokenValuePair {
+                    token: DocComment,
+                    value: lexeme.text,
+                    span: lexeme.span,
+                },
+                LexemeKind::Declaration | LexemeKind::Directive => {
+                    // Every alternative of the regex is a named group, so a
+                    // capture always names its token.
+                    let pair = re
+                        .captures(&lexeme.text)
+                        .and_then(|capture| TokenValuePair::from_capture(&capture).ok());
+
+                    match pair {
+                        Some(pair) => TokenValuePair {
+                            span: lexeme.span,
+                            ..pair
+                        },
+                        None => TokenValuePair {
+                            token: Unknown,
+                            value: lexeme.text,
+                            span: lexeme.span,
+                        },
+                    }
+                }
+            })
             .collect::<Vec<TokenValuePair>>()
     }
 }
@@ -0,0 +1,36 @@
+/**
+ * @brief A node of a list.
+ * @since 1.2
+ */
+typedef struct node {
+    /** The next node. */
+    struct node *next;
+    union {
+        int i;
+        float f;
+    } value;
+    unsigned flags : 3;
+} node_t;
+
+/** Status codes. */
+enum status { OK = 0, /**< Success. */ ERR };
+
+/** A callback. */
+typedef void (*visit_fn)(node_t *node, void *ctx);
+
+/**
+ * Pushes a value onto a list, see {@link node_t the node type}.
+ *
+ * @param[in,out] head The list
+ * @param value The value
+ * @retval 0 on success
+ * @retval -1 if out of memory
+ * @deprecated Use list_insert instead.
+ */
+static inline int list_push(node_t **head, const char *value, ...);
+
+/** The larger of two values. */
+#define MAX(a, b) ((a) > (b) ? (a) : (b))
+
+/** The default visitor. */
+extern visit_fn default_visit;
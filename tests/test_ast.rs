@@ -13,11 +13,16 @@ mod tests {
                 comment: "Test function".to_string(),
                 params: vec![],
                 retval: None,
+                ..Default::default()
             }),
             ParsedToken::Function(Function {
                 name: "test".to_string(),
-                return_type: "void".to_string(),
+                return_type: Type {
+                    name: "void".to_string(),
+                    ..Default::default()
+                },
                 params: vec![],
+                ..Default::default()
             }),
         ];
 
@@ -30,4 +35,75 @@ mod tests {
             NodeTypes::Function(_)
         ));
     }
+
+    #[test]
+    fn test_build_ast_spans() {
+        let tokens = doc_buildr::token::Token::tokenize("\n/** Doc. */\nint f(void);\n");
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert!(errors.is_empty());
+        let mut ast = AST::build_ast(&parsed);
+        ast.set_file("f.h");
+
+        let node = ast.get_iter().next().unwrap();
+        assert_eq!(node.get_span().line, 3);
+        assert_eq!(node.get_comment().unwrap().span.line, 2);
+        assert_eq!(ast.get_file(), Some("f.h"));
+    }
+
+    #[test]
+    fn test_build_ast_unknown_declaration() {
+        let tokens = doc_buildr::token::Token::tokenize(
+            "/** Doc for assert */\n_Static_assert(1, \"x\");\nint b(void);\n",
+        );
+        let (parsed, _) = parse_tokens(&tokens);
+        let ast = AST::build_ast(&parsed);
+
+        assert_eq!(ast.get_iter().count(), 1);
+        let node = ast.get_iter().next().unwrap();
+        assert!(matches!(node.get_value(), Some(NodeTypes::Function(f)) if f.name == "b"));
+        assert!(node.get_comment().is_none());
+    }
+
+    #[test]
+    fn test_anonymous_enum_title() {
+        let tokens = doc_buildr::token::Token::tokenize("/** Flags. */\nenum { FOO = 1 };\n");
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert!(errors.is_empty());
+        let ast = AST::build_ast(&parsed);
+
+        let value = ast.get_iter().next().unwrap().get_value().as_ref().unwrap();
+        assert_eq!(value.title(), "(anonymous)");
+        assert_eq!(value.anchor(), "enum-anonymous");
+    }
+
+    #[test]
+    fn test_symbol_index_and_links() {
+        let tokens = doc_buildr::token::Token::tokenize(
+            "/** A list. */\ntypedef struct list { int n; } list_t;\n\
+             /** Uses {@link list_t} and {@link nowhere}. */\nvoid f(void);\n",
+        );
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert!(errors.is_empty());
+
+        let ast = AST::build_ast(&parsed);
+        let index = SymbolIndex::from_ast(&ast);
+        assert_eq!(index.anchor("list"), Some("struct-list_t-struct-list"));
+        assert_eq!(index.anchor("list_t"), Some("struct-list_t-struct-list"));
+        assert_eq!(index.anchor("f"), Some("function-f"));
+        assert_eq!(
+            index.type_anchor("struct  list"),
+            Some("struct-list_t-struct-list")
+        );
+        assert_eq!(
+            index.type_anchor("list_t"),
+            Some("struct-list_t-struct-list")
+        );
+        assert_eq!(index.type_anchor("list"), None);
+        assert_eq!(index.type_anchor("f"), None);
+
+        let warnings = ast.resolve_links(&index);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].message, "unresolved link to `nowhere`");
+        assert_eq!(warnings[0].span.line, 3);
+    }
 }
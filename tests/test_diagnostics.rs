@@ -0,0 +1,57 @@
+use doc_buildr::diagnostics::*;
+use doc_buildr::error::*;
+use doc_buildr::span::Span;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typedef_error() -> ParseError {
+        let span = Span {
+            start: 13,
+            end: 25,
+            line: 2,
+            column: 2,
+        };
+
+        ParseError::new(ParseErrorKind::MissingName, "typedef declares no name")
+            .with_span(span)
+            .with_documented(true)
+    }
+
+    #[test]
+    fn test_render_human() {
+        let src = "int f(void);\n\ttypedef int;\n";
+        let diagnostic = Diagnostic::from(&typedef_error());
+
+        let report = diagnostic.render("e.h", src, MessageFormat::Human, false);
+        assert_eq!(
+            report,
+            "error: typedef declares no name\n \
+             --> e.h:2:2\n  \
+             |\n\
+             2 | \ttypedef int;\n  \
+             | \t^^^^^^^^^^^^\n  \
+             |\n  \
+             = help: give the declaration a name to document it\n"
+        );
+
+        let colored = diagnostic.render("e.h", src, MessageFormat::Human, true);
+        assert!(colored.starts_with("\x1b[1;31merror\x1b[0m: "));
+
+        let undocumented = Diagnostic::from(&typedef_error().with_documented(false));
+        assert_eq!(undocumented.severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_render_json() {
+        let diagnostic = Diagnostic::warning("unresolved \"link\"", Span::default());
+
+        assert_eq!(
+            diagnostic.render("dir\\e.h", "", MessageFormat::Json, true),
+            "{\"severity\":\"warning\",\"message\":\"unresolved \\\"link\\\"\",\
+             \"file\":\"dir\\\\e.h\",\"line\":0,\"column\":0,\"start\":0,\"end\":0,\
+             \"help\":null}"
+        );
+    }
+}
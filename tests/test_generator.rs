@@ -0,0 +1,106 @@
+use doc_buildr::ast::*;
+use doc_buildr::entity;
+use doc_buildr::generator::*;
+use doc_buildr::parser::*;
+use doc_buildr::token::Token;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Lists the functions and structs of every module, one per line.
+    #[derive(Default)]
+    struct Outline {
+        lines: Vec<String>,
+    }
+
+    impl Generator for Outline {
+        fn begin_module(&mut self, ast: &AST) {
+            self.lines
+                .push(format!("module {}", ast.get_file().unwrap_or("?")));
+        }
+
+        fn visit_function(&mut self, _node: &Node, function: &entity::Function) {
+            self.lines.push(format!("  fn {}", function.name));
+        }
+
+        fn visit_struct(&mut self, node: &Node, _struct: &entity::Struct) {
+            let brief = node.get_comment().map_or("", |c| c.comment.trim());
+            self.lines.push(format!("  struct {}", brief));
+        }
+
+        fn end_module(&mut self, _ast: &AST) {
+            self.lines.push(String::from("end"));
+        }
+
+        fn finish(&mut self) -> Output {
+            Output::Document(self.lines.join("\n"))
+        }
+    }
+
+    #[test]
+    fn test_generate_custom() {
+        let (parsed, _) = parse_tokens(&Token::tokenize(
+            "/** A point. */\nstruct point { int x; };\n#define N 1\nint f(void);\n",
+        ));
+        let mut ast = AST::build_ast(&parsed);
+        ast.set_file("p.h");
+
+        let output = generate(&mut Outline::default(), &[ast]);
+
+        assert_eq!(
+            output,
+            Output::Document(String::from("module p.h\n  struct A point.\n  fn f\nend"))
+        );
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut registry = Registry::with_builtins();
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            vec!["markdown", "html", "json", "man"]
+        );
+
+        registry.register("outline", |_| Box::<Outline>::default());
+        registry.register("json", |_| Box::<Outline>::default());
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            vec!["markdown", "html", "json", "man", "outline"]
+        );
+
+        let index = SymbolIndex::new();
+        let mut json = registry.create("json", &index).unwrap();
+        assert_eq!(
+            generate(json.as_mut(), &[]),
+            Output::Document(String::new())
+        );
+        assert!(registry.create("pdf", &index).is_none());
+    }
+
+    #[test]
+    fn test_registry_builtins() {
+        let (parsed, _) = parse_tokens(&Token::tokenize("/** Adds. */\nint add(int a);\n"));
+        let mut ast = AST::build_ast(&parsed);
+        ast.set_file("src/add.c");
+
+        let index = SymbolIndex::from_ast(&ast);
+        let registry = Registry::with_builtins();
+
+        let mut markdown = registry.create("markdown", &index).unwrap();
+        let Output::Document(md) = generate(markdown.as_mut(), std::slice::from_ref(&ast)) else {
+            panic!("markdown is a single document");
+        };
+        assert!(md.starts_with("# Module add\n\n## Function `add`\n\n"));
+
+        let mut html = registry.create("html", &index).unwrap();
+        let Output::Site(pages) = generate(html.as_mut(), std::slice::from_ref(&ast)) else {
+            panic!("html is a site");
+        };
+        assert_eq!(pages[1].path, "add.html");
+        assert_eq!(html.default_dir(), "html");
+
+        let man = registry.create("man", &index).unwrap();
+        assert_eq!(man.default_dir(), ".");
+    }
+}
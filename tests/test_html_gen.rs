@@ -0,0 +1,114 @@
+use doc_buildr::ast::*;
+use doc_buildr::generator::Page;
+use doc_buildr::html_gen::*;
+use doc_buildr::parser::*;
+use doc_buildr::token::Token;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(src: &str) -> Vec<ParsedToken> {
+        let (parsed, errors) = parse_tokens(&Token::tokenize(src));
+        assert!(errors.is_empty());
+        parsed
+    }
+
+    fn page<'p>(pages: &'p [Page], path: &str) -> &'p str {
+        &pages
+            .iter()
+            .find(|page| page.path == path)
+            .unwrap()
+            .contents
+    }
+
+    #[test]
+    fn test_generate_site() {
+        let list = parse(
+            "/** A list. */\ntypedef struct list { int n; } list_t;\n\
+             /** Pushes onto a list. */\nint list_push(list_t *l, const char *s);\n",
+        );
+        let util = parse("/** Clears it, see {@link list_push}. */\nvoid clear(list_t *l);\n");
+
+        let mut asts = vec![AST::build_ast(&list), AST::build_ast(&util)];
+        asts[0].set_file("src/list.h");
+        asts[1].set_file("src/util.h");
+
+        let mut index = SymbolIndex::new();
+        for ast in &asts {
+            index.add(ast);
+        }
+
+        let pages = generate_site(&asts, &index);
+        let paths = pages
+            .iter()
+            .map(|page| page.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![
+                "index.html",
+                "list.html",
+                "util.html",
+                "search-index.js",
+                "search.js",
+                "style.css"
+            ]
+        );
+
+        let list = page(&pages, "list.html");
+        assert!(list.contains("<section class=\"item\" id=\"function-list_push\">"));
+        assert!(list.contains("<li class=\"current\"><a href=\"list.html\">list</a></li>"));
+        assert!(list.contains("<li><a href=\"util.html#function-clear\">clear</a></li>"));
+        assert!(list.contains(
+            "<pre class=\"signature\"><code><span class=\"kw\">int</span> list_push(\
+             <a class=\"type\" href=\"list.html#struct-list_t-struct-list\">list_t</a> *l, \
+             <span class=\"kw\">const</span> <span class=\"kw\">char</span> *s)</code></pre>"
+        ));
+
+        let util = page(&pages, "util.html");
+        assert!(util.contains(
+            "<p>Clears it, see <a href=\"list.html#function-list_push\">list_push</a>.</p>"
+        ));
+        assert!(util.contains("<p class=\"source\">Defined in <code>src/util.h:2</code></p>"));
+
+        assert!(page(&pages, "search-index.js").contains(
+            "{\"name\":\"clear\",\"kind\":\"function\",\"module\":\"util\",\
+             \"href\":\"util.html#function-clear\",\"desc\":\"Clears it, see list_push.\"}"
+        ));
+    }
+
+    #[test]
+    fn test_generate_site_escapes_html() {
+        let parsed = parse(
+            "/**\n * Returns 1 if a < b & b > 0.\n *\n * ```\n * a <b>\n * ```\n */\n\
+             int f(int a, int b);\n",
+        );
+        let mut ast = AST::build_ast(&parsed);
+        ast.set_file("index.h");
+
+        let pages = generate_site(&[ast], &SymbolIndex::new());
+        let html = page(&pages, "index-2.html");
+
+        assert!(html.contains("<p>Returns 1 if a &lt; b &amp; b &gt; 0.</p>"));
+        assert!(html.contains("<pre><code>a &lt;b&gt;</code></pre>"));
+
+        let parsed = parse(
+            "/** @retval <script>x</script> Never. */
+int g(void);
+",
+        );
+        let mut ast = AST::build_ast(&parsed);
+        ast.set_file("a\"b.h");
+
+        let pages = generate_site(&[ast], &SymbolIndex::new());
+        let html = page(&pages, "a\"b.html");
+        assert!(html.contains("<td><code>&lt;script&gt;x&lt;/script&gt;</code></td>"));
+        assert!(html.contains("<a href=\"a&quot;b.html\">a&quot;b</a>"));
+        assert!(html.contains("<a href=\"a&quot;b.html#function-g\">g</a>"));
+        assert!(!html.contains("<script>x"));
+
+        let js = page(&pages, "search-index.js");
+        assert!(js.contains("\"href\":\"a\\\"b.html#function-g\""));
+    }
+}
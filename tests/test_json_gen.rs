@@ -0,0 +1,51 @@
+use doc_buildr::ast::*;
+use doc_buildr::json_gen::*;
+use doc_buildr::parser::*;
+use doc_buildr::token::Token;
+
+use std::fs;
+use std::path::Path;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Compares the JSON generated for `tests/golden/<name>.h` with
+    /// `tests/golden/<name>.json`, rewriting the latter instead if the
+    /// `BLESS` environment variable is set.
+    fn check_golden(name: &str) {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+        let src = fs::read_to_string(dir.join(format!("{}.h", name))).unwrap();
+
+        let (parsed, errors) = parse_tokens(&Token::tokenize(&src));
+        assert!(errors.is_empty());
+
+        let mut ast = AST::build_ast(&parsed);
+        ast.set_file(&format!("{}.h", name));
+        let json = generate_json(&[ast]);
+
+        let golden = dir.join(format!("{}.json", name));
+
+        if std::env::var_os("BLESS").is_some() {
+            fs::write(&golden, &json).unwrap();
+        }
+
+        assert_eq!(json, fs::read_to_string(golden).unwrap());
+    }
+
+    #[test]
+    fn test_generate_json_golden() {
+        check_golden("api");
+    }
+
+    #[test]
+    fn test_generate_json_empty() {
+        assert_eq!(
+            generate_json(&[]),
+            format!(
+                "{{\n  \"schema_version\": {},\n  \"modules\": []\n}}\n",
+                SCHEMA_VERSION
+            )
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use doc_buildr::lexer::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lex_declarations() {
+        let input = r#"
+        /** A point. */
+        struct point { int x; int y; };
+        int add(int a, int b) { return a + b; }
+        extern "C" {
+        void f(void);
+        }
+        "#;
+
+        let lexemes = Lexer::new(input).collect::<Vec<_>>();
+        assert_eq!(lexemes.len(), 4);
+        assert_eq!(lexemes[0].kind, LexemeKind::DocComment);
+        assert_eq!(lexemes[1].text, "struct point { int x; int y; };");
+        assert_eq!(lexemes[2].text, "int add(int a, int b) {}");
+        assert_eq!(lexemes[3].text, "void f(void);");
+    }
+
+    #[test]
+    fn test_lex_conditionals() {
+        let input = r#"
+        #if 0
+        int disabled(void);
+        #else
+        int enabled(void);
+        #endif
+        #ifdef FOO
+        int maybe(void);
+        #endif
+        "#;
+
+        let lexemes = Lexer::new(input).map(|l| l.text).collect::<Vec<_>>();
+        assert_eq!(lexemes, vec!["int enabled(void);", "int maybe(void);"]);
+    }
+
+    #[test]
+    fn test_lex_defines() {
+        let input = r#"
+        #ifndef POOL_H
+        #define POOL_H
+        #define URL "http://example.com" // home page
+        #define MIN(a, b) \
+            ((a) < (b) ? (a) : (b))
+        struct s {
+        #define INNER 1
+            int x;
+        };
+        #endif
+        "#;
+
+        let lexemes = Lexer::new(input).collect::<Vec<_>>();
+        assert_eq!(lexemes.len(), 3);
+        assert_eq!(lexemes[0].kind, LexemeKind::Directive);
+        assert_eq!(lexemes[0].text, r#"#define URL "http://example.com""#);
+        assert!(lexemes[1].text.starts_with("#define MIN(a, b)"));
+        assert!(lexemes[1].text.ends_with("((a) < (b) ? (a) : (b))"));
+        assert_eq!(lexemes[2].kind, LexemeKind::Declaration);
+    }
+
+    #[test]
+    fn test_lex_attributed_struct() {
+        let input = r#"
+        typedef struct __attribute__((packed)) { int a; } packed_t;
+        int f(void) __attribute__((cold)) { return 0; }
+        "#;
+
+        let lexemes = Lexer::new(input).map(|l| l.text).collect::<Vec<_>>();
+        assert_eq!(
+            lexemes,
+            vec![
+                "typedef struct __attribute__((packed)) { int a; } packed_t;",
+                "int f(void) __attribute__((cold)) {}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lex_spans() {
+        let input = "/** Doc. */\n  int  x; // trailing\n\tstruct s {\n  int y;\n};\n";
+
+        let lexemes = Lexer::new(input).collect::<Vec<_>>();
+        assert_eq!(lexemes.len(), 3);
+        assert_eq!((lexemes[0].span.line, lexemes[0].span.column), (1, 1));
+        assert_eq!((lexemes[1].span.line, lexemes[1].span.column), (2, 3));
+        assert_eq!(
+            &input[lexemes[1].span.start..lexemes[1].span.end],
+            "int  x;"
+        );
+        assert_eq!((lexemes[2].span.line, lexemes[2].span.column), (3, 2));
+        assert!(input[lexemes[2].span.start..lexemes[2].span.end].ends_with("};"));
+    }
+}
@@ -0,0 +1,124 @@
+use doc_buildr::ast::*;
+use doc_buildr::generator::Page;
+use doc_buildr::man_gen::*;
+use doc_buildr::parser::*;
+use doc_buildr::token::Token;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(files: &[(&str, &str)]) -> Vec<Page> {
+        let parsed = files
+            .iter()
+            .map(|(_, src)| {
+                let (parsed, errors) = parse_tokens(&Token::tokenize(src));
+                assert!(errors.is_empty());
+                parsed
+            })
+            .collect::<Vec<_>>();
+
+        let mut asts = parsed.iter().map(AST::build_ast).collect::<Vec<_>>();
+        let mut index = SymbolIndex::new();
+
+        for (ast, (file, _)) in asts.iter_mut().zip(files) {
+            ast.set_file(file);
+            index.add(ast);
+        }
+
+        generate_man(&asts, &index)
+    }
+
+    fn page<'p>(pages: &'p [Page], path: &str) -> &'p str {
+        &pages
+            .iter()
+            .find(|page| page.path == path)
+            .unwrap()
+            .contents
+    }
+
+    #[test]
+    fn test_function_page() {
+        let pages = build(&[(
+            "include/list.h",
+            "/**\n * Pushes onto a list, see {@link list_pop}.\n * .5 is `-1`.\n * @param[out] l The list\n * @return The new length\n * @retval -1 on error\n * @note .5 is rounded\n * @see list_pop()\n * @see realloc\n */\nint list_push(int *l);\n/** Pops. */\nint list_pop(int *l);\n",
+        )]);
+
+        assert_eq!(
+            page(&pages, "man3/list_push.3"),
+            ".TH \"LIST_PUSH\" 3 \"\" \"list\" \"Library Functions Manual\"\n\
+             .SH NAME\n\
+             list_push \\- Pushes onto a list, see \\fBlist_pop\\fR(3).\n\
+             .SH SYNOPSIS\n\
+             .nf\n\
+             .B #include <list.h>\n\
+             .PP\n\
+             .B \"int list_push(int *l);\"\n\
+             .fi\n\
+             .SH DESCRIPTION\n\
+             .PP\n\
+             Pushes onto a list, see \\fBlist_pop\\fR(3).\n\
+             \\&.5 is \\fB\\-1\\fR.\n\
+             .TP\n\
+             .IR \"l\" \" (out)\"\n\
+             The list\n\
+             .SH RETURN VALUE\n\
+             .PP\n\
+             The new length\n\
+             .TP\n\
+             .B \"\\-1\"\n\
+             on error\n\
+             .SH NOTES\n\
+             .PP\n\
+             \\&.5 is rounded\n\
+             .SH SEE ALSO\n\
+             .BR \"list_pop\" (3),\n\
+             realloc,\n\
+             .BR \"list\" (3)\n"
+        );
+    }
+
+    #[test]
+    fn test_module_pages() {
+        let pages = build(&[
+            (
+                "src/list.h",
+                "/** A list. */\ntypedef struct list { int n; } list_t;\n/** Makes a list. */\nlist_t *list(void);\n#define N 1\n",
+            ),
+            ("src/other/list.h", "/** Makes another list. */\nlist_t *list(void);\n"),
+        ]);
+
+        let paths = pages
+            .iter()
+            .map(|page| page.path.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["man3/list.3", "man3/list-2.3", "man3/list-3.3"]);
+
+        assert_eq!(
+            page(&pages, "man3/list-2.3"),
+            ".TH \"LIST\\-2\" 3 \"\" \"list\\-2\" \"Library Functions Manual\"\n\
+             .SH NAME\n\
+             list\\-2 \\- declarations of \\fBlist.h\\fR\n\
+             .SH SYNOPSIS\n\
+             .nf\n\
+             .B #include <list.h>\n\
+             .fi\n\
+             .SH DESCRIPTION\n\
+             .SS Functions\n\
+             .TP\n\
+             .BR \"list\" (3)\n\
+             Makes a list.\n\
+             .SS Types\n\
+             .TP\n\
+             .B \"list_t (struct list)\"\n\
+             A list.\n\
+             .SS Macros\n\
+             .TP\n\
+             .B \"N\"\n\
+             .SH SEE ALSO\n\
+             .BR \"list\" (3)\n"
+        );
+        assert!(page(&pages, "man3/list.3").contains("\n.BR \"list\\-2\" (3)\n"));
+        assert!(page(&pages, "man3/list-3.3").contains(".BR \"list\" (3)\nMakes another list.\n"));
+    }
+}
@@ -15,15 +15,28 @@ mod tests {
                 params: vec![Param {
                     name: "x".to_string(),
                     description: "Input parameter".to_string(),
+                    direction: None,
                 }],
                 retval: Some(Return {
                     description: "Output value".to_string(),
                 }),
+                ..Default::default()
             }),
             ParsedToken::Function(Function {
                 name: "test".to_string(),
-                return_type: "int".to_string(),
-                params: vec!["int x".to_string()],
+                return_type: Type {
+                    name: "int".to_string(),
+                    ..Default::default()
+                },
+                params: vec![Parameter {
+                    ty: Type {
+                        name: "int".to_string(),
+                        ..Default::default()
+                    },
+                    name: Some("x".to_string()),
+                    ..Default::default()
+                }],
+                ..Default::default()
             }),
         ];
 
@@ -37,4 +50,191 @@ mod tests {
         assert!(md.contains("**Returns**:"));
         assert!(md.contains("`int`: Output value"));
     }
+
+    #[test]
+    fn test_generate_md_enum() {
+        let tokens = vec![ParsedToken::Enum(Enum {
+            name: "flags".to_string(),
+            variants: vec![
+                Variant {
+                    name: "READ".to_string(),
+                    value: Some("1 << 0".to_string()),
+                    description: Some("Readable".to_string()),
+                },
+                Variant {
+                    name: "ALL".to_string(),
+                    value: Some("READ | WRITE".to_string()),
+                    description: None,
+                },
+            ],
+            ..Default::default()
+        })];
+
+        let ast = AST::build_ast(&tokens);
+        let md = generate_md(&ast);
+
+        assert!(md.contains("## Enum `flags`"));
+        assert!(md.contains("| Variant | Value | Description |"));
+        assert!(md.contains("| `READ` | `1 << 0` | Readable |"));
+        assert!(md.contains("| `ALL` | `READ \\| WRITE` |  |"));
+    }
+
+    #[test]
+    fn test_generate_md_macro() {
+        let tokens = vec![
+            ParsedToken::DocComment(DocComment {
+                comment: "Logs a message".to_string(),
+                params: vec![Param {
+                    name: "fmt".to_string(),
+                    description: "Format string".to_string(),
+                    direction: None,
+                }],
+                retval: None,
+                ..Default::default()
+            }),
+            ParsedToken::Macro(Macro {
+                name: "LOG".to_string(),
+                params: Some(vec!["fmt".to_string(), "...".to_string()]),
+                value: "do { fprintf(stderr, \"[%s:%d] \", __FILE__, __LINE__); } while (0)"
+                    .to_string(),
+                ..Default::default()
+            }),
+        ];
+
+        let ast = AST::build_ast(&tokens);
+        let md = generate_md(&ast);
+
+        assert!(md.contains("## Macro `LOG`"));
+        assert!(md.contains("#define LOG(fmt, ...) ...\n"));
+        assert!(md.contains("- `fmt`: Format string"));
+    }
+
+    #[test]
+    fn test_generate_md_location() {
+        let tokens = doc_buildr::token::Token::tokenize("/** Doc. */\nint f(void);\n");
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert!(errors.is_empty());
+
+        let mut ast = AST::build_ast(&parsed);
+        assert!(generate_md(&ast).contains("*Defined at line 2*"));
+
+        ast.set_file("f.h");
+        assert!(generate_md(&ast).contains("*Defined in `f.h:2`*"));
+    }
+
+    #[test]
+    fn test_generate_md_block_tags() {
+        let tokens = vec![
+            ParsedToken::DocComment(DocComment {
+                comment: "Frees a buffer.\n".to_string(),
+                brief: Some("Releases memory.".to_string()),
+                deprecated: Some(String::new()),
+                notes: vec!["Safe to call with NULL.".to_string()],
+                see: vec!["buf_alloc".to_string(), "buf_realloc".to_string()],
+                ..Default::default()
+            }),
+            ParsedToken::Variable(Variable {
+                name: "pool".to_string(),
+                ty: Type {
+                    name: "int".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        ];
+
+        let ast = AST::build_ast(&tokens);
+        let md = generate_md(&ast);
+
+        assert!(md.contains("**Deprecated**\n\n**Brief**: Releases memory.\n\nFrees a buffer."));
+        assert!(md.contains("**Note**: Safe to call with NULL.\n"));
+        assert!(md.contains("**See also**:\n- buf_alloc\n- buf_realloc\n"));
+    }
+
+    #[test]
+    fn test_generate_md_param_direction() {
+        let tokens = doc_buildr::token::Token::tokenize(
+            "/**\n * Reads.\n * @param[in] fd The file\n * @param[out] buf The buffer\n * @param[in,out] len The length\n */\nint rd(int fd, char *buf, int *len);\n",
+        );
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert!(errors.is_empty());
+
+        let ast = AST::build_ast(&parsed);
+        let md = generate_md(&ast);
+
+        assert!(md.contains("- `fd` *(in)*: The file\n"));
+        assert!(md.contains("- `buf` *(out)*: The buffer\n"));
+        assert!(md.contains("- `len` *(in, out)*: The length\n"));
+    }
+
+    #[test]
+    fn test_generate_md_retvals() {
+        let tokens = doc_buildr::token::Token::tokenize(
+            "/**\n * Opens a file.\n * @return A status code\n * @retval 0 Success\n * @retval -EINVAL Bad\n *         argument\n */\nint op(const char *path);\n",
+        );
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert!(errors.is_empty());
+
+        let ast = AST::build_ast(&parsed);
+        let md = generate_md(&ast);
+
+        assert!(md.contains(
+            "**Returns**:\n\n`int`: A status code\n\n\
+             | Value | Description |\n\
+             | --- | --- |\n\
+             | `0` | Success |\n\
+             | `-EINVAL` | Bad argument |\n"
+        ));
+    }
+
+    #[test]
+    fn test_generate_md_code_block() {
+        let tokens = doc_buildr::token::Token::tokenize(
+            "/**\n * Example:\n *   indented\n * \\code\n * if (x) {\n *     y();\n * }\n * \\endcode\n */\nvoid f(void);\n",
+        );
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert!(errors.is_empty());
+
+        let ast = AST::build_ast(&parsed);
+        let md = generate_md(&ast);
+
+        assert!(md.contains("&nbsp;&nbsp;indented\n```c\nif (x) {\n    y();\n}\n```\n"));
+    }
+
+    #[test]
+    fn test_generate_md_links() {
+        let tokens = doc_buildr::token::Token::tokenize(
+            "/** Pushes. */\nvoid list_push(int x);\n/** See {@link list_push}, {@link list_push the pusher} and @ref gone. */\nint g;\n",
+        );
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert!(errors.is_empty());
+
+        let ast = AST::build_ast(&parsed);
+        let md = generate_md(&ast);
+
+        assert!(md.contains(
+            "See [list_push](#function-list_push), [the pusher](#function-list_push) and gone."
+        ));
+    }
+
+    #[test]
+    fn test_generate_md_type_links() {
+        let tokens = doc_buildr::token::Token::tokenize(
+            "/** A node. */\nstruct node { struct node *next; };\n\
+             /** A list. */\ntypedef struct list { int n; } list_t;\n\
+             /** Pushes. */\nvoid list_push(list_t *l, int list);\n\
+             /** Plain. */\nvoid f(int x);\n",
+        );
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert!(errors.is_empty());
+
+        let ast = AST::build_ast(&parsed);
+        let md = generate_md(&ast);
+
+        assert!(md.contains("- [`struct node`](#struct-node)` *next`\n"));
+        assert!(md.contains(
+            "<pre><code>void list_push(<a href=\"#struct-list_t-struct-list\">list_t</a> *l, int list)</code></pre>"
+        ));
+        assert!(md.contains("```c\nvoid f(int x)\n```"));
+    }
 }
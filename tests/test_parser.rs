@@ -1,5 +1,8 @@
 use doc_buildr::entity::*;
+use doc_buildr::error::*;
 use doc_buildr::parser::*;
+use doc_buildr::span::Span;
+use doc_buildr::token::{Token, TokenValuePair};
 
 #[cfg(test)]
 mod tests {
@@ -31,8 +34,114 @@ mod tests {
         let function_str = "int add(int x, int y)";
         let function = Function::parse(function_str).unwrap();
         assert_eq!(function.name, "add");
-        assert_eq!(function.return_type, "int");
-        assert_eq!(function.params, vec!["int x", "int y"]);
+        assert_eq!(function.return_type.name, "int");
+        let params = function
+            .params
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(params, vec!["int x", "int y"]);
+    }
+
+    #[test]
+    fn test_parse_function_complex_return_type() {
+        let function_str =
+            "static inline const struct node *find(struct list *l, int key) __attribute__((pure));";
+        let function = Function::parse(function_str).unwrap();
+        assert_eq!(function.name, "find");
+        assert_eq!(function.storage.as_deref(), Some("static"));
+        assert_eq!(function.specifiers, vec!["inline"]);
+        assert_eq!(function.attributes, vec!["__attribute__((pure))"]);
+        assert_eq!(function.return_type.qualifiers, vec!["const"]);
+        assert_eq!(function.return_type.name, "struct node");
+        assert_eq!(function.return_type.pointers, vec![Vec::<String>::new()]);
+        assert_eq!(
+            function.return_type.declare("find"),
+            "const struct node *find"
+        );
+    }
+
+    #[test]
+    fn test_parse_function_params() {
+        let function_str =
+            "int f(const char *name, int arr[10], void (*cb)(int), struct node *, ...);";
+        let function = Function::parse(function_str).unwrap();
+        let params = &function.params;
+        assert_eq!(params.len(), 5);
+
+        assert_eq!(params[0].name.as_deref(), Some("name"));
+        assert_eq!(params[0].ty.qualifiers, vec!["const"]);
+        assert_eq!(params[0].ty.pointers, vec![Vec::<String>::new()]);
+
+        assert_eq!(params[1].name.as_deref(), Some("arr"));
+        assert_eq!(params[1].arrays, vec!["10"]);
+
+        assert_eq!(params[2].name.as_deref(), Some("cb"));
+        assert_eq!(params[2].function.as_ref().unwrap().len(), 1);
+        assert_eq!(params[2].to_string(), "void (*cb)(int)");
+
+        assert_eq!(params[3].name, None);
+        assert_eq!(params[3].ty.name, "struct node");
+
+        assert!(params[4].is_variadic());
+        assert!(Function::parse("void g(void);").unwrap().params.is_empty());
+
+        let function = Function::parse("void h(void (*done)(void));").unwrap();
+        assert_eq!(function.params[0].to_string(), "void (*done)(void)");
+    }
+
+    #[test]
+    fn test_parse_function_pointer_to_array_param() {
+        let function = Function::parse("void m(int n, int (*mat)[4]);").unwrap();
+        let params = &function.params;
+        assert_eq!(params.len(), 2);
+
+        assert_eq!(params[1].name.as_deref(), Some("mat"));
+        assert_eq!(params[1].pointee_arrays, vec!["4"]);
+        assert!(params[1].function.is_none());
+        assert_eq!(params[1].to_string(), "int (*mat)[4]");
+
+        let function = Function::parse("void f(int a[sizeof(int)]);").unwrap();
+        assert_eq!(function.params[0].name.as_deref(), Some("a"));
+        assert_eq!(function.params[0].arrays, vec!["sizeof(int)"]);
+        assert!(function.params[0].function.is_none());
+
+        let error = Function::parse("void m(int n, int (*mat));").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::MalformedDeclaration);
+    }
+
+    #[test]
+    fn test_parse_pointer_qualifiers() {
+        let function =
+            Function::parse("char *const f(char *const p, const char *const *q);").unwrap();
+        assert_eq!(function.return_type.qualifiers, Vec::<String>::new());
+        assert_eq!(function.return_type.pointers, vec![vec!["const"]]);
+        assert_eq!(
+            function
+                .params
+                .iter()
+                .map(|p| p.to_string())
+                .collect::<Vec<_>>(),
+            vec!["char *const p", "const char *const *q"]
+        );
+        assert_eq!(function.return_type.declare("f"), "char *const f");
+
+        let struct_def =
+            Struct::parse("struct s { int *const *volatile pp; struct { int x; } *const inner; };")
+                .unwrap();
+        assert_eq!(
+            struct_def.members[0].ty.pointers,
+            vec![vec!["const"], vec!["volatile"]]
+        );
+        assert_eq!(struct_def.members[0].to_string(), "int *const *volatile pp");
+        assert_eq!(
+            struct_def.members[1].to_string(),
+            "struct { ... } *const inner"
+        );
+
+        let variable = Variable::parse("static const char *const names[4];").unwrap();
+        assert_eq!(variable.ty.qualifiers, vec!["const"]);
+        assert_eq!(variable.to_string(), "static const char *const names[4]");
     }
 
     #[test]
@@ -40,7 +149,62 @@ mod tests {
         let struct_str = "struct Point { int x; int y; }";
         let struct_def = Struct::parse(struct_str).unwrap();
         assert_eq!(struct_def.name, "Point");
-        assert_eq!(struct_def.members, vec!["int x", "int y"]);
+        let members = struct_def
+            .members
+            .iter()
+            .map(|m| m.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(members, vec!["int x", "int y"]);
+    }
+
+    #[test]
+    fn test_parse_struct_members() {
+        let struct_str = r#"struct packet {
+            unsigned int version : 4; /**< Protocol version. */
+            char name[32], *alias;
+            /** The payload. */
+            union {
+                int i;
+                struct { float x, y; } point;
+            } data;
+            void (*on_recv)(struct packet *);
+        };"#;
+        let struct_def = Struct::parse(struct_str).unwrap();
+        let members = &struct_def.members;
+        assert_eq!(members.len(), 5);
+
+        assert_eq!(members[0].name.as_deref(), Some("version"));
+        assert_eq!(members[0].bitfield.as_deref(), Some("4"));
+        assert_eq!(members[0].description.as_deref(), Some("Protocol version."));
+
+        assert_eq!(members[1].arrays, vec!["32"]);
+        assert_eq!(members[2].to_string(), "char *alias");
+
+        assert_eq!(members[3].name.as_deref(), Some("data"));
+        assert_eq!(members[3].description.as_deref(), Some("The payload."));
+        let data = members[3].aggregate.as_deref().unwrap();
+        assert!(matches!(data, Aggregate::Union(_)));
+        assert_eq!(data.members().len(), 2);
+        let point = data.members()[1].aggregate.as_deref().unwrap();
+        assert!(matches!(point, Aggregate::Struct(_)));
+        assert_eq!(point.members().len(), 2);
+        assert_eq!(members[3].to_string(), "union { ... } data");
+        assert_eq!(data.members()[1].to_string(), "struct { ... } point");
+
+        assert_eq!(members[4].to_string(), "void (*on_recv)(struct packet *)");
+    }
+
+    #[test]
+    fn test_parse_union() {
+        let union_str = "union value { int i; double d; char *s; };";
+        let union_def = Union::parse(union_str).unwrap();
+        assert_eq!(union_def.name, "value");
+        let members = union_def
+            .members
+            .iter()
+            .map(|m| m.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(members, vec!["int i", "double d", "char *s"]);
     }
 
     #[test]
@@ -48,6 +212,364 @@ mod tests {
         let enum_str = "enum Color { RED, GREEN, BLUE }";
         let enum_def = Enum::parse(enum_str).unwrap();
         assert_eq!(enum_def.name, "Color");
-        assert_eq!(enum_def.variants, vec!["RED", "GREEN", "BLUE"]);
+        let variants = enum_def
+            .variants
+            .iter()
+            .map(|v| &v.name)
+            .collect::<Vec<_>>();
+        assert_eq!(variants, vec!["RED", "GREEN", "BLUE"]);
+    }
+
+    #[test]
+    fn test_parse_enum_values_and_docs() {
+        let enum_str = r#"enum flags {
+            /** No flags. */
+            NONE,
+            READ = 1 << 0, /**< Readable, see {x}. */
+            WRITE = 1 << 1 /**< Writable. */
+        };"#;
+        let enum_def = Enum::parse(enum_str).unwrap();
+        let variants = &enum_def.variants;
+        assert_eq!(variants.len(), 3);
+        assert_eq!(variants[0].value, None);
+        assert_eq!(variants[0].description.as_deref(), Some("No flags."));
+        assert_eq!(variants[1].value.as_deref(), Some("1 << 0"));
+        assert_eq!(
+            variants[1].description.as_deref(),
+            Some("Readable, see {x}.")
+        );
+        assert_eq!(variants[2].name, "WRITE");
+        assert_eq!(variants[2].description.as_deref(), Some("Writable."));
+    }
+
+    #[test]
+    fn test_parse_typedef() {
+        let typedef = Typedef::parse("typedef unsigned int u32;").unwrap();
+        assert_eq!(typedef.name, "u32");
+        assert_eq!(typedef.ty.name, "unsigned int");
+
+        let typedef = Typedef::parse("typedef void (*handler_t)(int sig);").unwrap();
+        assert_eq!(typedef.name, "handler_t");
+        assert_eq!(typedef.function.as_ref().unwrap().len(), 1);
+        assert_eq!(typedef.to_string(), "typedef void (*handler_t)(int sig)");
+    }
+
+    #[test]
+    fn test_parse_typedef_alias() {
+        let struct_def = Struct::parse("typedef struct { int x, y; } point_t;").unwrap();
+        assert_eq!(struct_def.name, "");
+        assert_eq!(struct_def.alias.as_deref(), Some("point_t"));
+
+        let enum_def = Enum::parse("typedef enum color { RED } color_t, *color_p;").unwrap();
+        assert_eq!(enum_def.name, "color");
+        assert_eq!(enum_def.alias.as_deref(), Some("color_t"));
+
+        let struct_def = Struct::parse("struct point { int x; } origin;").unwrap();
+        assert_eq!(struct_def.alias, None);
+    }
+
+    #[test]
+    fn test_parse_macro() {
+        let object = Macro::parse("#define MAX_CONN 64").unwrap();
+        assert_eq!(object.name, "MAX_CONN");
+        assert_eq!(object.params, None);
+        assert_eq!(object.value, "64");
+
+        let function = Macro::parse("#define MIN(a,b)   ((a) < (b) ? (a) : (b))").unwrap();
+        assert_eq!(function.name, "MIN");
+        assert_eq!(
+            function.params,
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        assert_eq!(
+            function.to_string(),
+            "#define MIN(a, b) ((a) < (b) ? (a) : (b))"
+        );
+
+        let not_function = Macro::parse("#define ONE (1)").unwrap();
+        assert_eq!(not_function.params, None);
+        assert_eq!(not_function.value, "(1)");
+    }
+
+    #[test]
+    fn test_parse_variable() {
+        let variable = Variable::parse("extern const char *version_string;").unwrap();
+        assert_eq!(variable.name, "version_string");
+        assert_eq!(variable.storage.as_deref(), Some("extern"));
+        assert_eq!(variable.ty.to_string(), "const char *");
+        assert_eq!(variable.initializer, None);
+
+        let variable = Variable::parse("static const int table[16] = {\n 1,  2, 3 };").unwrap();
+        assert_eq!(variable.name, "table");
+        assert_eq!(variable.arrays, vec!["16"]);
+        assert_eq!(variable.initializer.as_deref(), Some("{ 1, 2, 3 }"));
+        assert_eq!(
+            variable.to_string(),
+            "static const int table[16] = { 1, 2, 3 }"
+        );
+
+        let error = Variable::parse("struct node;").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::MissingName);
+    }
+
+    #[test]
+    fn test_parse_tokens_variable_declarators() {
+        let tokens = Token::tokenize(
+            "static int g1, *g2 = &g1;\nvoid (*handlers[4])(int);\nint (*arr)[3];\n",
+        );
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert!(errors.is_empty());
+
+        let variables = parsed
+            .iter()
+            .map(|token| match token {
+                ParsedToken::Variable(x) => (x.to_string(), x.span.line),
+                _ => panic!("expected a variable"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            variables,
+            vec![
+                (String::from("static int g1"), 1),
+                (String::from("static int *g2 = &g1"), 1),
+                (String::from("void (*handlers[4])(int)"), 2),
+                (String::from("int (*arr)[3]"), 3),
+            ]
+        );
+        assert!(matches!(&parsed[3], ParsedToken::Variable(x) if x.pointee_arrays == ["3"]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = DocComment::parse("/* Not a doc comment").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::MalformedComment);
+
+        let error = Struct::parse("struct s { int x;").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::MalformedDeclaration);
+
+        let error = Function::parse("DECLARE_THING(foo);").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::MalformedDeclaration);
+
+        let error = Typedef::parse("typedef int;").unwrap_err();
+        assert_eq!(error.kind, ParseErrorKind::MissingName);
+    }
+
+    #[test]
+    fn test_parse_tokens_collects_errors() {
+        let span = |line| Span {
+            line,
+            column: 1,
+            ..Default::default()
+        };
+        let pairs = [
+            TokenValuePair {
+                token: Token::Function,
+                value: String::from("int broken;"),
+                span: span(1),
+            },
+            TokenValuePair {
+                token: Token::Function,
+                value: String::from("int ok(void);"),
+                span: span(2),
+            },
+            TokenValuePair {
+                token: Token::Enum,
+                value: String::from("enum e { A"),
+                span: span(3),
+            },
+            TokenValuePair {
+                token: Token::Unknown,
+                value: String::from("_Static_assert(1, \"x\");"),
+                span: span(4),
+            },
+        ];
+
+        let (parsed, errors) = parse_tokens(&pairs);
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].span().line, 2);
+        assert!(matches!(parsed[1], ParsedToken::Unknown(_)));
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[0].kind, ParseErrorKind::MalformedDeclaration);
+        assert_eq!(errors[0].span.line, 1);
+        assert_eq!(errors[1].span.line, 3);
+        assert!(errors[1].to_string().starts_with("3:1: "));
+        assert_eq!(errors[2].kind, ParseErrorKind::UnknownToken);
+        assert_eq!(errors[2].span.line, 4);
+        assert!(errors.iter().all(|error| !error.documented));
+    }
+
+    #[test]
+    fn test_parse_tokens_documented_errors() {
+        let tokens = Token::tokenize(
+            "/** Checked. */\n_Static_assert(1, \"x\");\n_Static_assert(2, \"y\");\n\
+             /** Broken. */\ntypedef int;\n",
+        );
+        let (_, errors) = parse_tokens(&tokens);
+
+        let documented = errors
+            .iter()
+            .map(|error| (error.span.line, error.documented))
+            .collect::<Vec<_>>();
+        assert_eq!(documented, vec![(2, true), (3, false), (5, true)]);
+    }
+
+    #[test]
+    fn test_parse_doc_comment_brief_paragraph() {
+        let doc = DocComment::parse(
+            "/**\n * @brief Adds two numbers.\n *\n * Detailed text\n * over two lines.\n \
+             * @return The sum\n *\n * More detail.\n */",
+        )
+        .unwrap();
+        assert_eq!(doc.brief.as_deref(), Some("Adds two numbers."));
+        assert_eq!(doc.retval.unwrap().description, "The sum");
+        assert_eq!(
+            doc.comment,
+            " Detailed text\n over two lines.\n More detail.\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_doc_comment_block_tags() {
+        let comment = r#"/**
+         * Frees a buffer.
+         * @brief Releases memory.
+         * @param buf The buffer
+         * @note Safe to call with NULL.
+         * @note Idempotent.
+         * @warning Do not use the buffer
+         *          afterwards.
+         * @see buf_alloc
+         * @since 1.2
+         * @deprecated
+         * @throws ENOMEM never
+         * @author Jane Doe
+         * @todo Poison freed memory.
+         * @pre buf was allocated by buf_alloc.
+         * @post buf is invalid.
+         */"#;
+
+        let doc = DocComment::parse(comment).unwrap();
+        assert_eq!(doc.comment.trim(), "Frees a buffer.");
+        assert_eq!(doc.brief.as_deref(), Some("Releases memory."));
+        assert_eq!(doc.params.len(), 1);
+        assert_eq!(doc.params[0].description, "The buffer");
+        assert_eq!(doc.notes, vec!["Safe to call with NULL.", "Idempotent."]);
+        assert_eq!(doc.warnings, vec!["Do not use the buffer afterwards."]);
+        assert_eq!(doc.see, vec!["buf_alloc"]);
+        assert_eq!(doc.since.as_deref(), Some("1.2"));
+        assert_eq!(doc.deprecated.as_deref(), Some(""));
+        assert_eq!(doc.throws, vec!["ENOMEM never"]);
+        assert_eq!(doc.authors, vec!["Jane Doe"]);
+        assert_eq!(doc.todos, vec!["Poison freed memory."]);
+        assert_eq!(doc.pre, vec!["buf was allocated by buf_alloc."]);
+        assert_eq!(doc.post, vec!["buf is invalid."]);
+    }
+
+    #[test]
+    fn test_parse_doc_comment_doxygen_commands() {
+        let comment = r#"/**
+         * \brief Copies a string.
+         * \param dst The destination
+         * @arg src The source
+         * \returns The number of bytes copied
+         */"#;
+
+        let doc = DocComment::parse(comment).unwrap();
+        assert_eq!(doc.brief.as_deref(), Some("Copies a string."));
+        assert_eq!(doc.params.len(), 2);
+        assert_eq!(doc.params[0].name, "dst");
+        assert_eq!(doc.params[1].name, "src");
+        assert_eq!(
+            doc.retval.unwrap().description,
+            "The number of bytes copied"
+        );
+
+        let doc = DocComment::parse("/** Runs.\n * @result Zero on success */").unwrap();
+        assert_eq!(doc.comment.trim(), "Runs.");
+        assert_eq!(doc.retval.unwrap().description, "Zero on success");
+    }
+
+    #[test]
+    fn test_parse_doc_comment_param_direction() {
+        let comment = r#"/**
+         * @param[in] src The source
+         * @param [out] dst The destination
+         * @param[in, out] len The length
+         * @param[sideways] x Unknown direction
+         * @param n The count
+         */"#;
+
+        let doc = DocComment::parse(comment).unwrap();
+        let directions = doc.params.iter().map(|p| p.direction).collect::<Vec<_>>();
+        assert_eq!(
+            directions,
+            vec![
+                Some(Direction::In),
+                Some(Direction::Out),
+                Some(Direction::InOut),
+                None,
+                None
+            ]
+        );
+        assert_eq!(doc.params[1].name, "dst");
+        assert_eq!(doc.params[2].description, "The length");
+    }
+
+    #[test]
+    fn test_parse_doc_comment_code_blocks() {
+        let comment = r#"/**
+         * Pushes a value.
+         * @code{.c}
+         *   list_push(list, 1);
+         *
+         *   if (x) {
+         *       list_push(list, 2);
+         *   }
+         * @endcode
+         * @param list The list
+         * ```sh
+         * make  check
+         * ```
+         */"#;
+
+        let doc = DocComment::parse(comment).unwrap();
+        assert_eq!(
+            doc.comment,
+            " Pushes a value.\n\
+             ```c\n\
+             list_push(list, 1);\n\
+             \n\
+             if (x) {\n    list_push(list, 2);\n}\n\
+             ```\n\
+             ```sh\nmake  check\n```\n"
+        );
+        assert_eq!(doc.params[0].description, "The list");
+    }
+
+    #[test]
+    fn test_parse_doc_comment_links() {
+        let comment = r#"/**
+         * Pushes onto a {@link list}, see {@link list_pop() the popper}.
+         * @see @ref list_peek and \ref list_len "its length"
+         * Mail me@ref.org
+         */"#;
+
+        let doc = DocComment::parse(comment).unwrap();
+        let links = doc
+            .links
+            .iter()
+            .map(|link| (link.target.as_str(), link.label.as_deref()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            links,
+            vec![
+                ("list", None),
+                ("list_pop", Some("the popper")),
+                ("list_peek", None),
+                ("list_len", Some("its length")),
+            ]
+        );
     }
 }
@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::PathBuf;
+
+use doc_buildr::ast::*;
+use doc_buildr::generator::*;
+use doc_buildr::md_gen::MarkdownGenerator;
+use doc_buildr::parser::*;
+use doc_buildr::template::*;
+use doc_buildr::token::Token;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates an empty directory of templates, unique to a test, holding the
+    /// given files.
+    fn template_dir(test: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("doc-buildr-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        for (name, contents) in files {
+            fs::write(dir.join(name), contents).unwrap();
+        }
+
+        dir
+    }
+
+    #[test]
+    fn test_render_sections() {
+        let dir = template_dir(
+            "sections",
+            &[(
+                "list.md",
+                "{{! A list. }}\n{{#items}}\n{{^first}}, {{/first}}{{name|upper}}{{#last}}.{{/last}}\n{{/items}}\n{{^items}}\nNone\n{{/items}}\n",
+            )],
+        );
+        let templates = Templates::from_dir(&dir).unwrap();
+
+        let item = |name: &str| Value::Map(vec![("name", Value::text(name))]);
+        let context = Value::Map(vec![("items", Value::List(vec![item("a"), item("b|c")]))]);
+        assert_eq!(templates.render("list", &context), "A\n, B|C.\n");
+
+        let empty = Value::Map(vec![("items", Value::List(vec![]))]);
+        assert_eq!(templates.render("list", &empty), "None\n");
+        assert_eq!(templates.render("missing", &empty), "");
+    }
+
+    #[test]
+    fn test_override_templates() {
+        let dir = template_dir(
+            "override",
+            &[(
+                "function.md",
+                "### `{{name}}`\n\n{{doc.brief.text}}\n{{#doc.notes.items}}\n> **Note**: {{text}}\n{{/doc.notes.items}}\n\n**Thread safety**: {{^doc}}unknown{{/doc}}{{#doc}}see notes{{/doc}}\n",
+            )],
+        );
+        let templates = Templates::from_dir(&dir).unwrap();
+
+        let (parsed, _) = parse_tokens(&Token::tokenize(
+            "/**\n * @brief Adds.\n * @note Locks.\n * @note Blocks.\n */\nint add(int a);\nstruct s { int x; };\n",
+        ));
+        let ast = AST::build_ast(&parsed);
+        let index = SymbolIndex::from_ast(&ast);
+
+        let mut generator = MarkdownGenerator::with_templates(&index, templates);
+        let Output::Document(md) = generate(&mut generator, &[ast]) else {
+            panic!("markdown is a single document");
+        };
+
+        assert_eq!(
+            md,
+            "# Module \n\n### `add`\n\nAdds.\n> **Note**: Locks.\n> **Note**: Blocks.\n\n**Thread safety**: see notes\n\n## Struct `s`\n\nNo documentation available\n\n**Members**:\n- `int x`\n\n*Defined at line 7*\n"
+        );
+    }
+
+    #[test]
+    fn test_template_errors() {
+        let unclosed = template_dir("unclosed", &[("enum.md", "{{#variants}}\n{{name}}\n")]);
+        let err = Templates::from_dir(&unclosed).unwrap_err();
+        assert_eq!(err.line, 1);
+        assert!(err.name.ends_with("enum.md"));
+        assert_eq!(err.message, "`variants` is never closed");
+
+        let partial = template_dir("partial", &[("enum.md", "{{> footer}}\n")]);
+        let err = Templates::from_dir(&partial).unwrap_err();
+        assert_eq!(err.to_string(), "enum: no template named `footer`");
+
+        let filter = template_dir("filter", &[("enum.md", "{{name|shout}}\n")]);
+        let err = Templates::from_dir(&filter).unwrap_err();
+        assert_eq!(err.message, "unknown filter `shout`");
+    }
+}
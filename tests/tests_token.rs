@@ -1,3 +1,4 @@
+use doc_buildr::parser::parse_tokens;
 use doc_buildr::token::Token;
 
 #[cfg(test)]
@@ -21,4 +22,165 @@ mod tests {
         assert!(matches!(tokens[0].token, Token::DocComment));
         assert!(matches!(tokens[1].token, Token::Function));
     }
+
+    #[test]
+    fn test_tokenize_skips_comments_and_literals() {
+        let input = r#"
+        const char *s = "/** not a doc comment */ int fake(void);";
+        // struct commented { int x; };
+        /* int hidden(void); */
+        char c = '{';
+        /** Real doc. */
+        struct real { int x; };
+        "#;
+
+        let tokens = Token::tokenize(input);
+        assert_eq!(tokens.len(), 4);
+        assert!(matches!(tokens[0].token, Token::Variable));
+        assert!(matches!(tokens[1].token, Token::Variable));
+        assert!(matches!(tokens[2].token, Token::DocComment));
+        assert!(matches!(tokens[3].token, Token::Struct));
+        assert_eq!(tokens[3].value, "struct real { int x; };");
+    }
+
+    #[test]
+    fn test_tokenize_skips_disabled_code() {
+        let input = r#"
+        #if 0
+        /** Old API. */
+        int old_api(void);
+        #endif
+        int new_api(void);
+        "#;
+
+        let tokens = Token::tokenize(input);
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].value, "int new_api(void);");
+    }
+
+    #[test]
+    fn test_tokenize_complex_function_declarations() {
+        let input = r#"
+        char *strdup(const char *s);
+        unsigned long hash(const void *data, size_t len);
+        static inline int f(void);
+        const struct node *find(struct list *l, int (*cmp)(const void *, const void *));
+        void fatal(const char *fmt, ...) __attribute__((noreturn, format(printf, 1, 2)));
+        "#;
+
+        let tokens = Token::tokenize(input);
+        assert_eq!(tokens.len(), 5);
+        assert!(tokens.iter().all(|t| matches!(t.token, Token::Function)));
+        assert_eq!(tokens[2].value, "static inline int f(void);");
+    }
+
+    #[test]
+    fn test_tokenize_function_needs_return_type() {
+        let input = r#"
+        DECLARE_THING(foo);
+        _Static_assert(sizeof(int) == 4, "x");
+        "#;
+
+        let tokens = Token::tokenize(input);
+        assert_eq!(tokens.len(), 2);
+        assert!(tokens.iter().all(|t| matches!(t.token, Token::Unknown)));
+    }
+
+    #[test]
+    fn test_tokenize_function_definitions() {
+        let input = r#"
+        /** Adds two integers. */
+        int add(int a, int b)
+        {
+            if (a > 0) { return a + b; }
+            return b + a;
+        }
+        "#;
+
+        let tokens = Token::tokenize(input);
+        assert_eq!(tokens.len(), 2);
+        assert!(matches!(tokens[1].token, Token::Function));
+        assert_eq!(tokens[1].value, "int add(int a, int b)\n        {}");
+    }
+
+    #[test]
+    fn test_tokenize_nested_struct() {
+        let input = r#"
+        struct outer {
+            struct { int a; } inner;
+            int b;
+        };
+        "#;
+
+        let tokens = Token::tokenize(input);
+        assert_eq!(tokens.len(), 1);
+        assert!(matches!(tokens[0].token, Token::Struct));
+        assert!(tokens[0].value.ends_with("int b;\n        };"));
+    }
+
+    #[test]
+    fn test_tokenize_union() {
+        let input = r#"
+        /** A value. */
+        typedef union value { int i; struct { short lo, hi; } parts; } value_t;
+        "#;
+
+        let tokens = Token::tokenize(input);
+        assert_eq!(tokens.len(), 2);
+        assert!(matches!(tokens[1].token, Token::Union));
+    }
+
+    #[test]
+    fn test_tokenize_anonymous_aggregates() {
+        let input = r#"
+        enum { FOO = 1, BAR = 2 };
+        struct { int x; } origin;
+        union { int i; float f; };
+        "#;
+
+        let tokens = Token::tokenize(input);
+        assert_eq!(tokens.len(), 3);
+        assert!(matches!(tokens[0].token, Token::Enum));
+        assert!(matches!(tokens[1].token, Token::Struct));
+        assert!(matches!(tokens[2].token, Token::Union));
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert_eq!(parsed.len(), 3);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_tokenize_typedefs() {
+        let input = r#"
+        typedef unsigned int u32;
+        typedef void (*handler_t)(int);
+        typedef struct { int x, y; } point_t;
+        typedef enum { RED, GREEN } color_t;
+        "#;
+
+        let tokens = Token::tokenize(input);
+        assert_eq!(tokens.len(), 4);
+        assert!(matches!(tokens[0].token, Token::Typedef));
+        assert!(matches!(tokens[1].token, Token::Typedef));
+        assert!(matches!(tokens[2].token, Token::Struct));
+        assert!(matches!(tokens[3].token, Token::Enum));
+    }
+
+    #[test]
+    fn test_tokenize_variables() {
+        let input = r#"
+        extern const char *version_string;
+        static const int table[16] = { 1, 2, 3 };
+        void (*on_exit)(int) = NULL;
+        void (*handlers[4])(int);
+        int count = compute(1, 2), total;
+        struct node;
+        "#;
+
+        let tokens = Token::tokenize(input);
+        assert_eq!(tokens.len(), 6);
+        assert!(tokens.iter().all(|t| matches!(t.token, Token::Variable)));
+        let (parsed, errors) = parse_tokens(&tokens);
+        assert_eq!(parsed.len(), 6);
+        assert!(errors.is_empty());
+    }
 }